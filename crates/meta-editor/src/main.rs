@@ -1,12 +1,17 @@
 use std::{
 	collections::BTreeMap,
-	fs::{self, File},
+	fs::File,
 	ops::Range,
 	path::{Path, PathBuf},
 };
 
-use cofd_meta::{Op, PageKind, SectionMeta, SectionRange, SourceMeta};
-use cofd_miner::{hash, process_section, source::Section};
+use cofd_meta::{Op, PageKind, SectionMeta, SectionRange, Sidebars, SourceMeta};
+use cofd_miner::{
+	backend::PdfPages,
+	hash, identify, process_section_with,
+	source::{Normalizer, Section},
+	MetaRegistry,
+};
 use cofd_schema::prelude::BookInfo;
 use eframe::{
 	egui::{
@@ -31,7 +36,7 @@ fn main() -> eframe::Result<()> {
 struct MetaEditorApp {
 	meta: SourceMeta,
 	meta_path: PathBuf,
-	pages: BTreeMap<usize, Vec<String>>,
+	pages: PdfPages,
 	path: PathBuf,
 
 	selected_section: Option<usize>,
@@ -49,16 +54,16 @@ impl MetaEditorApp {
 		let path = PathBuf::from(args.get(1).unwrap());
 
 		let hash = hash::hash(&path).unwrap();
-
-		let (meta, meta_path) = fs::read_dir("meta")
-			.unwrap()
-			.filter_map(|entry| entry.ok().map(|e| e.path()))
-			.filter(|path| path.extension().map(|ext| ext.eq("json")).unwrap_or(false))
-			.map(|path| -> anyhow::Result<(SourceMeta, PathBuf)> {
-				Ok((serde_json::de::from_reader(File::open(&path)?)?, path))
+		let fingerprint = hash::fingerprint(&path).unwrap();
+
+		let registry = MetaRegistry::empty().with_dir("meta").unwrap();
+		let (meta, meta_path) = registry
+			.iter()
+			.find(|meta| {
+				meta.info.hash.eq(&hash)
+					|| meta.fingerprint.is_some_and(|other| other == fingerprint)
 			})
-			.filter_map(|r| r.ok())
-			.find(|(meta, _path)| meta.info.hash.eq(&hash))
+			.and_then(|meta| Some((meta.clone(), registry.path(meta.info.hash)?.to_owned())))
 			.unwrap_or_else(|| {
 				(
 					SourceMeta {
@@ -66,7 +71,13 @@ impl MetaEditorApp {
 							hash,
 							..Default::default()
 						},
+						fingerprint: Some(fingerprint),
 						sections: Vec::new(),
+						page_labels: Vec::new(),
+						hyphenation: Vec::new(),
+						normalization: BTreeMap::new(),
+						variants: Vec::new(),
+						identity: identify::identity(&path).unwrap(),
 					},
 					Path::new("meta")
 						.join(path.file_name().unwrap())
@@ -166,7 +177,13 @@ impl eframe::App for MetaEditorApp {
 							.get(self.selected_section.unwrap())
 							.unwrap();
 						self.section = Some(
-							process_section(&self.pages, section_def, self.show_full_text).unwrap(),
+							process_section_with(
+								&self.pages,
+								section_def,
+								self.show_full_text,
+								&Normalizer::new(&self.meta.normalization),
+							)
+							.unwrap(),
 						);
 
 						if let Some(selection) = self
@@ -260,6 +277,10 @@ impl eframe::App for MetaEditorApp {
 						range: None,
 						kind: PageKind::Merit(None),
 						ops: Vec::new(),
+						columns: None,
+						emphasis: false,
+						sidebars: Sidebars::Include,
+						ocr: false,
 					})
 				}
 
@@ -317,6 +338,18 @@ impl eframe::App for MetaEditorApp {
 							}
 						}
 
+						if let Some(location) = self
+							.last_range
+							.as_ref()
+							.and_then(|range| section.locate(section.to_original(range.start)))
+						{
+							let bbox = location.bbox;
+							ui.label(format!(
+								"Page {}: ({:.0}, {:.0}) – ({:.0}, {:.0})",
+								location.page, bbox.x0, bbox.y0, bbox.x1, bbox.y1
+							));
+						}
+
 						output.response.context_menu(|ui| {
 							if ui.button("Set range").clicked() {
 								if let Some(range) = &self.last_range {
@@ -0,0 +1,85 @@
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf},
+	process::ExitCode,
+};
+
+use cofd_meta::SourceMeta;
+use cofd_miner::{
+	backend::{self, PdfPages},
+	extract_meta_pages, hash, meta,
+	validate::{validate, validate_unique},
+};
+
+/// `meta-validate [meta dir] [pdf dir]`: checks every meta in `meta dir` (`meta` by
+/// default), and each printing with a PDF in `pdf dir` against its text.
+fn main() -> anyhow::Result<ExitCode> {
+	let args: Vec<_> = std::env::args().collect();
+	let meta_dir = PathBuf::from(args.get(1).map_or("meta", String::as_str));
+
+	let metas = meta::files(&meta_dir)?
+		.iter()
+		.map(meta::load)
+		.collect::<anyhow::Result<Vec<_>>>()?;
+	let pdfs = match args.get(2) {
+		Some(pdf_dir) => read_pdfs(Path::new(pdf_dir), &metas)?,
+		None => HashMap::new(),
+	};
+
+	let mut report = validate_unique(&metas);
+	for meta in &metas {
+		let printings = std::iter::once(Cow::Borrowed(meta)).chain(
+			meta.variants
+				.iter()
+				.map(|variant| Cow::Owned(meta.with_variant(variant))),
+		);
+		let mut checked = false;
+		for printing in printings {
+			if let Some(pages) = pdfs.get(&printing.info.hash) {
+				report.extend(validate(&printing, Some(pages)));
+				checked = true;
+			}
+		}
+		if !checked {
+			report.extend(validate(meta, None));
+		}
+	}
+
+	for problem in &report.problems {
+		println!("{problem}");
+	}
+	let errors = report.errors().count();
+	eprintln!(
+		"{} metas: {errors} errors, {} warnings",
+		metas.len(),
+		report.warnings().count()
+	);
+
+	Ok(if errors == 0 {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::FAILURE
+	})
+}
+
+/// The pages of each PDF in `dir` that's a printing of one of `metas`, by its hash.
+fn read_pdfs(dir: &Path, metas: &[SourceMeta]) -> anyhow::Result<HashMap<u64, PdfPages>> {
+	let mut pages = HashMap::new();
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		if !path.extension().is_some_and(|ext| ext == "pdf") {
+			continue;
+		}
+
+		let hash = hash::hash(&path)?;
+		if let Some(printing) = metas.iter().find_map(|meta| meta.for_hash(hash)) {
+			// Read the way the parse reads it, with the printing's column layouts and OCR.
+			let document = backend::default().open(&path)?;
+			pages.insert(hash, extract_meta_pages(document.as_ref(), &printing)?);
+		}
+	}
+
+	Ok(pages)
+}
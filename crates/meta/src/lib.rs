@@ -1,10 +1,14 @@
-use std::ops::{Range, RangeInclusive};
+use std::{
+	borrow::Cow,
+	collections::BTreeMap,
+	ops::{Range, RangeInclusive},
+};
 
 use cofd_schema::book::BookInfo;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-pub use crate::page_kind::PageKind;
+pub use crate::page_kind::{PageKind, TableKind};
 
 mod page_kind;
 
@@ -12,20 +16,31 @@ fn unnamed() -> String {
 	"Unnamed".to_owned()
 }
 
+/// A fix-up applied to a section's text before it is parsed.
+///
+/// Ops are applied in the order they are listed. All offsets are byte offsets
+/// into the text as left by the previous op, so the first op sees the section's
+/// `original` text and every later op sees the result of the ones before it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Op {
+	/// Replaces the bytes in `range` with `replace`.
 	Replace {
 		range: RangeInclusive<usize>,
 		replace: String,
 	},
+	/// Inserts `char` before the byte at `pos`. `pos` may equal the text length.
 	Insert {
 		pos: usize,
 		char: char,
 	},
+	/// Removes the bytes in `range`.
 	Delete {
 		range: RangeInclusive<usize>,
 	},
+	/// Cuts the bytes in `range` and pastes them at `pos`.
+	///
+	/// `pos` is measured before the cut and must not fall inside `range`.
 	Move {
 		range: RangeInclusive<usize>,
 		pos: usize,
@@ -44,6 +59,34 @@ pub enum SectionRange {
 	Regex(#[serde(with = "serde_regex")] Regex),
 }
 
+/// How the pages of a section are split into columns, for pages where detection gets it wrong.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Columns {
+	/// The number of columns; where they split is still detected.
+	Count(usize),
+	/// The x coordinates, in points, at which each column after the first starts.
+	Split(Vec<f32>),
+}
+
+/// What to do with the boxed text and sidebars on a section's pages.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Sidebars {
+	/// Keep them in the section's text, where they are on the page.
+	#[default]
+	Include,
+	/// Leave them out of the section.
+	Exclude,
+	/// Leave them out of the section's text, but keep each one as a note.
+	Notes,
+}
+
+impl Sidebars {
+	fn is_include(&self) -> bool {
+		*self == Self::Include
+	}
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SectionMeta {
 	#[serde(default = "unnamed", skip_serializing_if = "String::is_empty")]
@@ -54,12 +97,221 @@ pub struct SectionMeta {
 	pub kind: PageKind,
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub ops: Vec<Op>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub columns: Option<Columns>,
+	/// Whether italic text is wrapped in `*`, as Markdown emphasis.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub emphasis: bool,
+	#[serde(default, skip_serializing_if = "Sidebars::is_include")]
+	pub sidebars: Sidebars,
+	/// Whether the section's pages are read with OCR even though they have a text layer, for
+	/// scans whose text layer is garbage.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub ocr: bool,
+}
+
+/// The numbers printed on a book's pages, from one PDF page on.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PageLabel {
+	/// The zero-based index of the first PDF page this applies to.
+	pub start: usize,
+	/// The number printed on that page, counting up from there. `None` for pages without
+	/// arabic page numbers, like the cover or front matter numbered in roman numerals.
+	pub first: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SourceMeta {
 	pub info: BookInfo,
+	/// A hash of the book's text rather than its file, which every buyer's watermarked copy
+	/// shares. Matched when no meta has the file's exact hash.
+	#[serde(default, skip_serializing_if = "Option::is_none", with = "hex::option")]
+	pub fingerprint: Option<u64>,
 	pub sections: Vec<SectionMeta>,
+	/// Overrides the page labels read from the PDF, for books whose labels are missing or
+	/// don't match the printed numbers.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub page_labels: Vec<PageLabel>,
+	/// How words the book breaks across lines at a hyphen are spelled, like `God-Machine` or
+	/// `sleepwalker`, where the rest of its text doesn't settle it.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub hyphenation: Vec<String>,
+	/// Characters replaced in the book's text before it's parsed, on top of the built-in
+	/// table, like the private-use glyphs of its fonts.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub normalization: BTreeMap<char, String>,
+	/// Other printings of the book, like errata reprints or the print-on-demand version.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub variants: Vec<Variant>,
+	#[serde(default, skip_serializing_if = "Identity::is_empty")]
+	pub identity: Identity,
+}
+
+impl SourceMeta {
+	/// The meta for the printing whose file hash is `hash`, if it's one of this book's.
+	#[must_use]
+	pub fn for_hash(&self, hash: u64) -> Option<Cow<'_, Self>> {
+		if self.info.hash == hash {
+			Some(Cow::Borrowed(self))
+		} else {
+			self.variants
+				.iter()
+				.find(|variant| variant.hash == hash)
+				.map(|variant| Cow::Owned(self.with_variant(variant)))
+		}
+	}
+
+	/// The meta for the printing whose fingerprint is `fingerprint`, if it's one of this
+	/// book's.
+	#[must_use]
+	pub fn for_fingerprint(&self, fingerprint: u64) -> Option<Cow<'_, Self>> {
+		if self.fingerprint == Some(fingerprint) {
+			Some(Cow::Borrowed(self))
+		} else {
+			self.variants
+				.iter()
+				.find(|variant| variant.fingerprint == Some(fingerprint))
+				.map(|variant| Cow::Owned(self.with_variant(variant)))
+		}
+	}
+
+	/// This meta moved onto the pages of `variant`, with its hashes and without variants.
+	#[must_use]
+	pub fn with_variant(&self, variant: &Variant) -> Self {
+		let shift = |page: usize| page.saturating_add_signed(variant.page_offset);
+
+		let mut meta = self.clone();
+		meta.info.hash = variant.hash;
+		meta.fingerprint = variant.fingerprint;
+		meta.variants = Vec::new();
+		for section in &mut meta.sections {
+			section.pages = match variant.pages.get(&section.name) {
+				Some(pages) => pages.clone(),
+				None => shift(*section.pages.start())..=shift(*section.pages.end()),
+			};
+		}
+		if variant.page_labels.is_empty() {
+			for label in &mut meta.page_labels {
+				label.start = shift(label.start);
+			}
+		} else {
+			meta.page_labels.clone_from(&variant.page_labels);
+		}
+
+		meta
+	}
+}
+
+/// What a book's PDF looks like, for guessing which book a PDF is when no hash matches it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Identity {
+	/// The title in the PDF's document information, if it isn't the book's name.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub title: Option<String>,
+	/// The producer in the PDF's document information.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub producer: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub page_count: Option<usize>,
+	/// Fingerprints of the text of the book's first few pages, like its title and credits.
+	#[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex::vec")]
+	pub pages: Vec<u64>,
+}
+
+impl Identity {
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+}
+
+/// Another printing of a book, whose sections are on other pages.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Variant {
+	/// What sets this printing apart, like `Errata 1.1` or `POD`.
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub name: String,
+	#[serde(with = "hex")]
+	pub hash: u64,
+	#[serde(default, skip_serializing_if = "Option::is_none", with = "hex::option")]
+	pub fingerprint: Option<u64>,
+	/// How many pages later every section starts in this printing, or earlier if negative.
+	#[serde(default, skip_serializing_if = "is_zero")]
+	pub page_offset: isize,
+	/// The pages of the sections, by name, that moved by more or less than `page_offset`.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub pages: BTreeMap<String, RangeInclusive<usize>>,
+	/// Replaces the book's page labels, rather than shifting them by `page_offset`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub page_labels: Vec<PageLabel>,
 }
 
-impl SourceMeta {}
+fn is_zero(offset: &isize) -> bool {
+	*offset == 0
+}
+
+/// Writes a hash in hex, like [`BookInfo::hash`].
+mod hex {
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(hash: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&format!("{hash:X}"))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+		let hash = String::deserialize(deserializer)?;
+		u64::from_str_radix(&hash, 16).map_err(D::Error::custom)
+	}
+
+	pub mod vec {
+		use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+		pub fn serialize<S: Serializer>(hashes: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+			let mut seq = serializer.serialize_seq(Some(hashes.len()))?;
+			for hash in hashes {
+				seq.serialize_element(&format!("{hash:X}"))?;
+			}
+			seq.end()
+		}
+
+		pub fn deserialize<'de, D: Deserializer<'de>>(
+			deserializer: D,
+		) -> Result<Vec<u64>, D::Error> {
+			Vec::<String>::deserialize(deserializer)?
+				.iter()
+				.map(|hash| u64::from_str_radix(hash, 16).map_err(D::Error::custom))
+				.collect()
+		}
+	}
+
+	pub mod option {
+		use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+		pub fn serialize<S: Serializer>(
+			hash: &Option<u64>,
+			serializer: S,
+		) -> Result<S::Ok, S::Error> {
+			match hash {
+				Some(hash) => super::serialize(hash, serializer),
+				None => serializer.serialize_none(),
+			}
+		}
+
+		pub fn deserialize<'de, D: Deserializer<'de>>(
+			deserializer: D,
+		) -> Result<Option<u64>, D::Error> {
+			Option::<String>::deserialize(deserializer)?
+				.map(|hash| u64::from_str_radix(&hash, 16).map_err(D::Error::custom))
+				.transpose()
+		}
+	}
+}
+
+/// The number printed on the zero-based PDF page `index`, given labels sorted by `start`.
+#[must_use]
+pub fn printed_page(labels: &[PageLabel], index: usize) -> Option<usize> {
+	let i = labels.partition_point(|label| label.start <= index);
+	let label = labels.get(i.checked_sub(1)?)?;
+
+	label.first.map(|first| first + index - label.start)
+}
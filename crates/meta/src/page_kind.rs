@@ -12,6 +12,35 @@ pub enum PageKind {
 	),
 	MageSpell,
 	Gift(GiftKind),
+	Discipline(
+		/**
+		 * Name of the Discipline the powers belong to
+		 */
+		String,
+	),
+	Devotion,
+	Embed(
+		/**
+		 * Incarnation the Embeds are listed under
+		 */
+		Option<String>,
+	),
+	Exploit,
+	/// Ciphers, with the Embeds that are their Interlocks and their Final Truth.
+	Cipher,
+	/// Tables found on the section's pages, like the equipment charts.
+	Table(TableKind),
+	/// A kind with no built-in parser, handled by a parser registered downstream.
+	Custom(String),
+}
+
+/// What the rows of a [`PageKind::Table`] section are.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub enum TableKind {
+	Weapon,
+	Armor,
+	/// Any other table, kept as rows of text.
+	Other,
 }
 
 impl Default for PageKind {
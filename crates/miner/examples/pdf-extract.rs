@@ -89,16 +89,24 @@ fn main() -> Result<()> {
 				hash
 			}
 		})
-		.flat_map(|(path, hash)| cofd_miner::get_meta(hash).map(|meta| (path, meta)))
+		.flat_map(|(path, hash)| {
+			cofd_miner::get_meta_by_hash(hash)
+				.or_else(|| {
+					hash::fingerprint(&path)
+						.ok()
+						.and_then(cofd_miner::get_meta_by_fingerprint)
+				})
+				.map(|meta| (path, meta))
+		})
 		.flat_map(|(path, meta)| {
-			cofd_miner::parse_book_with_meta(&path, meta).map(|book| (path, book))
+			cofd_miner::parse_book_with_meta(&path, &meta).map(|parsed| (path, parsed))
 		})
-		.for_each(|(path, book)| {
+		.for_each(|(path, parsed)| {
 			let json_path = out_path
 				.join(path.file_name().unwrap())
 				.with_extension("json");
 
-			to_path_pretty(json_path, &book).unwrap();
+			to_path_pretty(json_path, &parsed).unwrap();
 		});
 
 	if cache.read().unwrap().dirty {
@@ -1,11 +1,23 @@
 use std::path::PathBuf;
 
-use cofd_miner::parse_book;
+use cofd_miner::{parse_book_with_registry, MetaRegistry};
 
 fn main() {
 	let args: Vec<_> = std::env::args().collect();
 	let path = PathBuf::from(args.get(1).unwrap());
 
-	let b = parse_book(path).unwrap();
-	print!("{}", serde_json::ser::to_string_pretty(&b).unwrap());
+	// Any further arguments are directories of metas that override the embedded ones.
+	let mut registry = MetaRegistry::embedded();
+	for dir in args.iter().skip(2) {
+		registry.load_dir(dir).unwrap();
+	}
+
+	let parsed = parse_book_with_registry(path, &registry).unwrap();
+	for diagnostic in &parsed.report.diagnostics {
+		eprintln!("{diagnostic}");
+	}
+	print!(
+		"{}",
+		serde_json::ser::to_string_pretty(&parsed.book).unwrap()
+	);
 }
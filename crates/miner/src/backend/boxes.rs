@@ -0,0 +1,70 @@
+use super::{PdfLine, Rect};
+
+/// The smallest box that can hold a sidebar, in points. Anything smaller is a rule, a table
+/// row's shading or a decoration.
+const MIN_BOX_WIDTH: f32 = 72.0;
+const MIN_BOX_HEIGHT: f32 = 24.0;
+/// A box covering this much of the page is the page's background.
+const MAX_BOX_AREA: f32 = 0.8;
+/// How far inside both edges of its column a block must be to count as boxed.
+#[cfg(feature = "mupdf")]
+const BOX_INSET: f32 = 8.0;
+/// The fewest lines an inset block needs, so a lone indented line isn't a box.
+#[cfg(feature = "mupdf")]
+const MIN_BOX_LINES: usize = 3;
+
+/// Marks the lines inside any of `boxes` as boxed text, ignoring boxes too small to hold
+/// text or so big they're the page's background.
+pub fn mark_boxed(lines: &mut [PdfLine], boxes: &[Rect], width: f32, height: f32) {
+	let boxes: Vec<&Rect> = boxes
+		.iter()
+		.filter(|rect| {
+			let (w, h) = (rect.x1 - rect.x0, rect.y1 - rect.y0);
+			w >= MIN_BOX_WIDTH && h >= MIN_BOX_HEIGHT && w * h < width * height * MAX_BOX_AREA
+		})
+		.collect();
+
+	for line in lines {
+		line.boxed = boxes.iter().any(|rect| contains(rect, center(&line.bbox)));
+	}
+}
+
+/// Text blocks set inside the edges of their column, the way sidebars without a drawn box
+/// are. Each block is given with the number of lines in it.
+#[cfg(feature = "mupdf")]
+pub fn inset_blocks(blocks: &[(Rect, usize)], lines: &[PdfLine]) -> Vec<Rect> {
+	blocks
+		.iter()
+		.filter(|(_, count)| *count >= MIN_BOX_LINES)
+		.filter(|(block, _)| {
+			let Some(column) = lines
+				.iter()
+				.find(|line| contains(block, center(&line.bbox)))
+				.map(|line| line.column)
+			else {
+				return false;
+			};
+
+			let (left, right) = lines
+				.iter()
+				.filter(|line| line.column == column)
+				.fold((f32::MAX, f32::MIN), |(left, right), line| {
+					(left.min(line.bbox.x0), right.max(line.bbox.x1))
+				});
+
+			block.x0 >= left + BOX_INSET && block.x1 <= right - BOX_INSET
+		})
+		.map(|(block, _)| *block)
+		.collect()
+}
+
+fn center(rect: &Rect) -> (f32, f32) {
+	(
+		f32::midpoint(rect.x0, rect.x1),
+		f32::midpoint(rect.y0, rect.y1),
+	)
+}
+
+fn contains(rect: &Rect, (x, y): (f32, f32)) -> bool {
+	rect.x0 <= x && x <= rect.x1 && rect.y0 <= y && y <= rect.y1
+}
@@ -0,0 +1,206 @@
+use cofd_meta::Columns;
+use cofd_schema::DOT_CHAR;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::PdfLine;
+
+static DOT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(&format!("^{DOT_CHAR} ")).unwrap());
+
+/// How far right of every other left edge a left edge must be to start a new column.
+const MIN_COLUMN_OFFSET: f32 = 60.0;
+/// A jump down the page of this many lines ends the page's content.
+const END_OF_CONTENT_LINES: f32 = 8.0;
+
+/// Turns the lines of one page into the text the parsers work on.
+///
+/// `raw_lines` are the lines as the backend found them, in reading order and with their
+/// bounding boxes. Blank lines and everything after a big jump down the page are dropped,
+/// and lines indented within their column get a leading tab. Columns are detected unless
+/// `columns` says otherwise.
+///
+/// Lines that are only a number, like page numbers, are kept as they are and left out of
+/// the layout, so the page numbers can be found and dropped across pages.
+pub fn layout_page(raw_lines: Vec<PdfLine>, columns: Option<&Columns>) -> Vec<PdfLine> {
+	let end_of_content = line_spacing(&raw_lines).map_or(100.0, |s| s * END_OF_CONTENT_LINES);
+
+	let mut last_y = 0.0;
+	let mut blank = false;
+
+	let mut lines = Vec::new();
+
+	for line in raw_lines {
+		let y_shift = (line.bbox.y0 - last_y).floor();
+
+		if y_shift.abs() > end_of_content {
+			blank = y_shift > 0.0; // End of Page Content
+		}
+		last_y = line.bbox.y0;
+
+		if is_number(&line) || !(blank || line.text.trim().is_empty()) {
+			lines.push(line);
+		}
+	}
+
+	let text_lines: Vec<PdfLine> = lines
+		.iter()
+		.filter(|line| !is_number(line))
+		.cloned()
+		.collect();
+	let splits = match columns {
+		Some(Columns::Split(splits)) => splits.clone(),
+		Some(Columns::Count(count)) => detect_splits(&text_lines, Some(*count)),
+		None => detect_splits(&text_lines, None),
+	};
+	let column = |x: f32| splits.iter().filter(|split| x + 1.0 >= **split).count();
+
+	let mut indents = vec![f32::MAX; splits.len() + 1];
+	for line in &text_lines {
+		let indent = &mut indents[column(line.bbox.x0)];
+		*indent = indent.min(line.bbox.x0);
+	}
+
+	let mut last_x = 0.0;
+	let mut last_indent = 0.0;
+
+	let mut last_has_dot = false;
+	// let mut dot_line_indent = f32::MAX;
+	// let mut dot_paragraph_indent = f32::MAX;
+	// let mut pre_dot_indent = f32::MAX;
+
+	let mut last_should_tab = false;
+	let mut last_line = String::new();
+
+	lines
+		.into_iter()
+		.map(|mut line| {
+			let x = line.bbox.x0;
+			line.column = column(x);
+			if is_number(&line) {
+				return line;
+			}
+			let min_x = indents[line.column];
+
+			let dot = DOT_REGEX.is_match(&line.text);
+			let indent = (x - min_x).floor();
+			let indent = if dot { indent.max(9.0) } else { indent };
+
+			#[allow(
+				clippy::if_same_then_else,
+				clippy::needless_bool,
+				clippy::nonminimal_bool
+			)]
+			let should_tab = if indent > last_indent {
+				if last_has_dot && !dot {
+					false
+				} else {
+					true
+				}
+			} else if indent < last_indent {
+				if dot {
+					true
+				} else {
+					false
+				}
+			} else if last_line.trim().ends_with(':') && dot {
+				true
+			} else {
+				last_should_tab
+			};
+
+			let indent = if indent == 0.0 && should_tab {
+				9.0
+			} else {
+				indent
+			};
+
+			last_x = x;
+
+			last_indent = indent;
+			last_should_tab = should_tab;
+			last_has_dot = dot;
+			last_line.clone_from(&line.text);
+
+			let prefix = if should_tab { "\t" } else { "" };
+
+			#[cfg(debug_assertions)]
+			if std::env::var("INDENT_DEBUG").is_ok() {
+				line.prefixed(&format!("{indent}{prefix}"))
+			} else {
+				line.prefixed(prefix)
+			}
+			#[cfg(not(debug_assertions))]
+			line.prefixed(prefix)
+		})
+		.collect()
+}
+
+/// Whether the line is nothing but a number, like a page number.
+fn is_number(line: &PdfLine) -> bool {
+	let text = line.text.trim();
+	!text.is_empty() && text.chars().all(char::is_numeric)
+}
+
+/// The most common distance between consecutive lines going down the page.
+fn line_spacing(lines: &[PdfLine]) -> Option<f32> {
+	let mut spacing: Vec<f32> = lines
+		.windows(2)
+		.map(|pair| pair[1].bbox.y0 - pair[0].bbox.y0)
+		.filter(|shift| *shift > 0.0)
+		.collect();
+	spacing.sort_by(f32::total_cmp);
+
+	spacing.get(spacing.len() / 2).copied()
+}
+
+/// Finds the x coordinates at which columns start.
+///
+/// A column starts at a left edge well to the right of the ones before it, that hardly any
+/// line crosses and that several lines start at. With a `count`, the best `count - 1` such
+/// edges are used whether or not they look like columns.
+fn detect_splits(lines: &[PdfLine], count: Option<usize>) -> Vec<f32> {
+	let mut edges: Vec<f32> = lines.iter().map(|line| line.bbox.x0).collect();
+	edges.sort_by(f32::total_cmp);
+	edges.dedup_by(|a, b| (*a - *b).abs() < 1.0);
+
+	let mut candidates: Vec<(f32, usize, usize)> = edges
+		.windows(2)
+		.filter(|pair| pair[1] - pair[0] >= MIN_COLUMN_OFFSET)
+		.map(|pair| {
+			let split = pair[1];
+			let crossing = lines
+				.iter()
+				.filter(|line| line.bbox.x0 < split - 1.0 && line.bbox.x1 > split + 1.0)
+				.count();
+			let starting = lines
+				.iter()
+				.filter(|line| {
+					line.bbox.x0 >= split - 1.0 && line.bbox.x0 < split + MIN_COLUMN_OFFSET
+				})
+				.count();
+
+			(split, crossing, starting)
+		})
+		.collect();
+
+	let mut splits: Vec<f32> = if let Some(count) = count {
+		candidates.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)));
+		candidates
+			.into_iter()
+			.take(count.saturating_sub(1))
+			.map(|(split, ..)| split)
+			.collect()
+	} else {
+		let min_lines = (lines.len() / 10).max(2);
+		candidates
+			.into_iter()
+			.filter(|(_, crossing, starting)| {
+				*crossing <= lines.len() / 10 && *starting >= min_lines
+			})
+			.map(|(split, ..)| split)
+			.collect()
+	};
+	splits.sort_by(f32::total_cmp);
+
+	splits
+}
@@ -1,27 +1,910 @@
-use std::{collections::BTreeMap, path::Path};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	path::Path,
+};
 
 use anyhow::anyhow;
-use lopdf::Document;
+use cofd_meta::{Columns, PageLabel};
+use lopdf::{
+	content::{Content, Operation},
+	Dictionary, Document, Object, ObjectId, Stream,
+};
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
+use regex::Regex;
 
-pub fn extract_pages(path: impl AsRef<Path>) -> anyhow::Result<PageText> {
-	let document = Document::load(path)?;
-
-	let pages = document
-		.get_pages()
-		.into_par_iter()
-		.map(
-			|(page_num, page_id): (u32, (u32, u16))| -> anyhow::Result<(u32, Vec<String>)> {
-				let text = document.extract_text(&[page_num]).map_err(|e| {
-					anyhow!("Failed to extract text from page {page_num} id={page_id:?}: {e:?}")
-				})?;
-				Ok((
-					page_num,
-					text.split('\n')
-						.map(|s| s.trim_end().to_owned())
-						.collect::<Vec<String>>(),
-				))
-			},
+use super::{
+	boxes::mark_boxed,
+	layout::layout_page,
+	running::{drop_running_text, number_pages},
+	table::detect_tables,
+	DocumentInfo, FontStyle, PdfLine, PdfPage, PdfPages, Rect, TextBackend, TextDocument,
+};
+
+static CMAP_TOKEN_REGEX: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"<(?<hex>[0-9A-Fa-f\s]*)>|\[|\]").unwrap());
+
+/// Width used for glyphs a font doesn't give a width for, in thousandths of an em.
+const DEFAULT_WIDTH: f32 = 500.0;
+/// A gap between runs wider than this many ems starts a new line, like a column gutter.
+const LINE_GAP: f32 = 3.0;
+/// A gap between runs wider than this many ems separates table cells.
+const CELL_GAP: f32 = 1.0;
+
+/// Extracts text with lopdf, without any system libraries.
+pub struct LoPdf;
+
+impl TextBackend for LoPdf {
+	fn open(&self, path: &Path) -> anyhow::Result<Box<dyn TextDocument>> {
+		let document = Document::load(path)?;
+		let pages = document.get_pages().into_values().collect();
+
+		Ok(Box::new(LoPdfDocument { document, pages }))
+	}
+}
+
+struct LoPdfDocument {
+	document: Document,
+	pages: Vec<ObjectId>,
+}
+
+impl TextDocument for LoPdfDocument {
+	fn page_count(&self) -> usize {
+		self.pages.len()
+	}
+
+	fn extract_page(&self, index: usize, columns: Option<&Columns>) -> anyhow::Result<PdfPage> {
+		let page_id = *self
+			.pages
+			.get(index)
+			.ok_or_else(|| anyhow!("No page {index}"))?;
+		let media_box = media_box(&self.document, page_id);
+		let (runs, boxes) = page_runs(&self.document, page_id).map_err(|e| {
+			anyhow!("Failed to extract text from page {index} id={page_id:?}: {e:?}")
+		})?;
+		let cells = to_lines(join_runs(runs.clone(), CELL_GAP), media_box.y1);
+		let mut lines = to_lines(join_runs(runs, LINE_GAP), media_box.y1);
+
+		let (width, height) = (media_box.x1 - media_box.x0, media_box.y1 - media_box.y0);
+		let boxes: Vec<Rect> = boxes
+			.into_iter()
+			.map(|rect| Rect {
+				x0: rect.x0,
+				y0: media_box.y1 - rect.y1,
+				x1: rect.x1,
+				y1: media_box.y1 - rect.y0,
+			})
+			.collect();
+		mark_boxed(&mut lines, &boxes, width, height);
+
+		Ok(PdfPage {
+			width,
+			height,
+			lines: layout_page(lines, columns),
+			tables: detect_tables(&cells),
+			number: None,
+			error: None,
+		})
+	}
+
+	fn page_text(&self, index: usize) -> anyhow::Result<Vec<String>> {
+		let page_id = *self
+			.pages
+			.get(index)
+			.ok_or_else(|| anyhow!("No page {index}"))?;
+		let (runs, _) = page_runs(&self.document, page_id).map_err(|e| {
+			anyhow!("Failed to extract text from page {index} id={page_id:?}: {e:?}")
+		})?;
+
+		Ok(join_runs(runs, LINE_GAP)
+			.into_iter()
+			.map(|run| run.line.text)
+			.collect())
+	}
+
+	fn extract_pages(
+		&self,
+		columns: &BTreeMap<usize, Columns>,
+		ocr: &BTreeSet<usize>,
+	) -> anyhow::Result<PdfPages> {
+		let mut pages = (0..self.pages.len())
+			.into_par_iter()
+			.map(|index| {
+				let page = self.read_page(index, columns.get(&index), ocr.contains(&index));
+				(index, page)
+			})
+			.collect();
+		number_pages(&mut pages);
+		drop_running_text(&mut pages);
+
+		Ok(pages)
+	}
+
+	fn info(&self) -> DocumentInfo {
+		let Ok(info) = self
+			.document
+			.trailer
+			.get_deref(b"Info", &self.document)
+			.and_then(Object::as_dict)
+		else {
+			return DocumentInfo::default();
+		};
+		let text = |key: &[u8]| {
+			info.get_deref(key, &self.document)
+				.and_then(Object::as_str)
+				.ok()
+				.map(decode_text_string)
+				.filter(|value| !value.trim().is_empty())
+		};
+
+		DocumentInfo {
+			title: text(b"Title"),
+			producer: text(b"Producer"),
+		}
+	}
+
+	fn page_labels(&self) -> Vec<PageLabel> {
+		let mut labels = Vec::new();
+		if let Ok(tree) = self
+			.document
+			.catalog()
+			.and_then(|catalog| catalog.get_deref(b"PageLabels", &self.document))
+			.and_then(Object::as_dict)
+		{
+			collect_page_labels(&self.document, tree, &mut labels);
+		}
+		labels.sort_by_key(|label| label.start);
+
+		labels
+	}
+}
+
+/// Reads the entries of a `PageLabels` number tree. Only labels that are plain arabic
+/// numbers get a `first` page number.
+fn collect_page_labels(document: &Document, node: &Dictionary, labels: &mut Vec<PageLabel>) {
+	if let Ok(nums) = node.get(b"Nums").and_then(Object::as_array) {
+		for pair in nums.chunks_exact(2) {
+			let (Some(start), Ok(label)) = (
+				pair[0]
+					.as_i64()
+					.ok()
+					.and_then(|start| usize::try_from(start).ok()),
+				document
+					.dereference(&pair[1])
+					.and_then(|(_, label)| label.as_dict()),
+			) else {
+				continue;
+			};
+
+			let decimal = label
+				.get(b"S")
+				.and_then(Object::as_name)
+				.is_ok_and(|style| style == b"D");
+			let prefixed = label
+				.get(b"P")
+				.and_then(Object::as_str)
+				.is_ok_and(|prefix| !prefix.is_empty());
+			let first = label
+				.get(b"St")
+				.and_then(Object::as_i64)
+				.map_or(Some(1), |first| usize::try_from(first).ok());
+
+			labels.push(PageLabel {
+				start,
+				first: first.filter(|_| decimal && !prefixed),
+			});
+		}
+	}
+
+	if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+		for kid in kids {
+			if let Ok(kid) = kid
+				.as_reference()
+				.and_then(|id| document.get_dictionary(id))
+			{
+				collect_page_labels(document, kid, labels);
+			}
+		}
+	}
+}
+
+/// A PDF text string, which is UTF-16 if it starts with a byte order mark and otherwise
+/// taken for Latin-1, close enough to `PDFDocEncoding` for titles.
+fn decode_text_string(bytes: &[u8]) -> String {
+	if let Some(utf16) = bytes.strip_prefix(&[0xfe, 0xff]) {
+		let units: Vec<u16> = utf16
+			.chunks_exact(2)
+			.map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+			.collect();
+		String::from_utf16_lossy(&units)
+	} else {
+		bytes.iter().map(|byte| char::from(*byte)).collect()
+	}
+}
+
+/// `[a b c d e f]`, as in the `cm` and `Tm` operators.
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+fn mul(m: &Matrix, n: &Matrix) -> Matrix {
+	[
+		m[0] * n[0] + m[1] * n[2],
+		m[0] * n[1] + m[1] * n[3],
+		m[2] * n[0] + m[3] * n[2],
+		m[2] * n[1] + m[3] * n[3],
+		m[4] * n[0] + m[5] * n[2] + n[4],
+		m[4] * n[1] + m[5] * n[3] + n[5],
+	]
+}
+
+fn translate(tx: f32, ty: f32) -> Matrix {
+	[1.0, 0.0, 0.0, 1.0, tx, ty]
+}
+
+/// The bounds of the rectangle `re` draws, after transforming it by `ctm`.
+fn transform_rect(ctm: &Matrix, x: f32, y: f32, width: f32, height: f32) -> Rect {
+	[
+		(x, y),
+		(x + width, y),
+		(x, y + height),
+		(x + width, y + height),
+	]
+	.into_iter()
+	.map(|(px, py)| {
+		(
+			px * ctm[0] + py * ctm[2] + ctm[4],
+			px * ctm[1] + py * ctm[3] + ctm[5],
 		)
+	})
+	.fold(
+		Rect {
+			x0: f32::MAX,
+			y0: f32::MAX,
+			x1: f32::MIN,
+			y1: f32::MIN,
+		},
+		|rect, (px, py)| Rect {
+			x0: rect.x0.min(px),
+			y0: rect.y0.min(py),
+			x1: rect.x1.max(px),
+			y1: rect.y1.max(py),
+		},
+	)
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+	ctm: Matrix,
+	font: Option<Vec<u8>>,
+	font_size: f32,
+	char_spacing: f32,
+	word_spacing: f32,
+	scale: f32,
+	leading: f32,
+}
+
+impl Default for GraphicsState {
+	fn default() -> Self {
+		Self {
+			ctm: IDENTITY,
+			font: None,
+			font_size: 0.0,
+			char_spacing: 0.0,
+			word_spacing: 0.0,
+			scale: 1.0,
+			leading: 0.0,
+		}
+	}
+}
+
+/// A run of text drawn by one text-showing operator, in PDF user space.
+#[derive(Clone)]
+struct Run {
+	x: f32,
+	y: f32,
+	end_x: f32,
+	size: f32,
+	line: PdfLine,
+}
+
+/// Font descriptor flags, from the PDF reference.
+const ITALIC_FLAG: i64 = 1 << 6;
+const SMALL_CAP_FLAG: i64 = 1 << 17;
+const FORCE_BOLD_FLAG: i64 = 1 << 18;
+
+struct Font {
+	name: String,
+	flags: i64,
+	encoding: String,
+	to_unicode: HashMap<u32, String>,
+	code_len: usize,
+	widths: HashMap<u32, f32>,
+	default_width: f32,
+}
+
+impl Font {
+	fn load(document: &Document, dict: &Dictionary) -> Self {
+		let deref = |obj| document.dereference(obj).map(|(_, obj)| obj).ok();
+		let composite = dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0");
+
+		let to_unicode = dict
+			.get(b"ToUnicode")
+			.ok()
+			.and_then(deref)
+			.and_then(|obj| obj.as_stream().ok())
+			.and_then(|stream| stream.decompressed_content().ok())
+			.map(|data| parse_cmap(&String::from_utf8_lossy(&data)))
+			.unwrap_or_default();
+
+		let descendant = dict
+			.get(b"DescendantFonts")
+			.ok()
+			.and_then(deref)
+			.and_then(|obj| obj.as_array().ok())
+			.and_then(|fonts| fonts.first())
+			.and_then(deref)
+			.and_then(|obj| obj.as_dict().ok());
+		let flags = descendant
+			.unwrap_or(dict)
+			.get(b"FontDescriptor")
+			.ok()
+			.and_then(deref)
+			.and_then(|obj| obj.as_dict().ok())
+			.and_then(|descriptor| descriptor.get(b"Flags").and_then(Object::as_i64).ok())
+			.unwrap_or(0);
+
+		let mut widths = HashMap::new();
+		let mut default_width = DEFAULT_WIDTH;
+		if composite {
+			if let Some(descendant) = descendant {
+				if let Some(dw) = descendant.get(b"DW").ok().and_then(|w| w.as_float().ok()) {
+					default_width = dw;
+				}
+				if let Some(w) = descendant
+					.get(b"W")
+					.ok()
+					.and_then(deref)
+					.and_then(|obj| obj.as_array().ok())
+				{
+					parse_cid_widths(document, w, &mut widths);
+				}
+			}
+		} else {
+			let first_char = dict.get(b"FirstChar").and_then(Object::as_i64).unwrap_or(0);
+			if let Some(w) = dict
+				.get(b"Widths")
+				.ok()
+				.and_then(deref)
+				.and_then(|obj| obj.as_array().ok())
+			{
+				for (code, width) in (first_char..).zip(w) {
+					if let (Ok(code), Some(width)) = (
+						u32::try_from(code),
+						deref(width).and_then(|w| w.as_float().ok()),
+					) {
+						widths.insert(code, width);
+					}
+				}
+			}
+		}
+
+		Self {
+			name: dict
+				.get(b"BaseFont")
+				.and_then(Object::as_name_str)
+				.unwrap_or_default()
+				.to_owned(),
+			flags,
+			encoding: dict.get_font_encoding().to_owned(),
+			to_unicode,
+			code_len: if composite { 2 } else { 1 },
+			widths,
+			default_width,
+		}
+	}
+
+	/// Splits `bytes` into character codes, paired with the text each one stands for.
+	fn decode(&self, bytes: &[u8]) -> Vec<(u32, String)> {
+		bytes
+			.chunks(self.code_len)
+			.map(|chunk| {
+				let code = chunk.iter().fold(0, |code, b| (code << 8) | u32::from(*b));
+				let text = self.to_unicode.get(&code).cloned().unwrap_or_else(|| {
+					if self.code_len == 1 {
+						Document::decode_text(Some(&self.encoding), chunk)
+					} else {
+						char::from_u32(code).map(String::from).unwrap_or_default()
+					}
+				});
+
+				(code, text)
+			})
+			.collect()
+	}
+
+	fn style(&self, size: f32) -> FontStyle {
+		let style = FontStyle::from_name(&self.name, size);
+
+		FontStyle {
+			bold: style.bold || self.flags & FORCE_BOLD_FLAG != 0,
+			italic: style.italic || self.flags & ITALIC_FLAG != 0,
+			small_caps: style.small_caps || self.flags & SMALL_CAP_FLAG != 0,
+			..style
+		}
+	}
+
+	fn width(&self, code: u32) -> f32 {
+		self.widths
+			.get(&code)
+			.copied()
+			.unwrap_or(self.default_width)
+	}
+}
+
+/// Reads a `/W` array: `c [w1 w2 ...]` or `c_first c_last w` entries.
+fn parse_cid_widths(document: &Document, w: &[Object], widths: &mut HashMap<u32, f32>) {
+	let number = |obj: &Object| document.dereference(obj).ok()?.1.as_float().ok();
+	let code = |obj: &Object| u32::try_from(document.dereference(obj).ok()?.1.as_i64().ok()?).ok();
+
+	let mut i = 0;
+	while i + 1 < w.len() {
+		let Some(first) = code(&w[i]) else {
+			break;
+		};
+
+		if let Ok(list) = w[i + 1].as_array() {
+			for (code, width) in (first..).zip(list) {
+				if let Some(width) = number(width) {
+					widths.insert(code, width);
+				}
+			}
+			i += 2;
+		} else if let (Some(last), Some(width)) = (code(&w[i + 1]), w.get(i + 2).and_then(number)) {
+			for code in first..=last {
+				widths.insert(code, width);
+			}
+			i += 3;
+		} else {
+			break;
+		}
+	}
+}
+
+/// Reads the `bfchar` and `bfrange` mappings of a `ToUnicode` character map.
+fn parse_cmap(data: &str) -> HashMap<u32, String> {
+	fn hex_bytes(hex: &str) -> Vec<u8> {
+		let hex: Vec<u8> = hex.bytes().filter(u8::is_ascii_hexdigit).collect();
+		hex.chunks(2)
+			.filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+			.collect()
+	}
+	fn hex_code(hex: &str) -> u32 {
+		hex_bytes(hex)
+			.into_iter()
+			.fold(0, |code, b| (code << 8) | u32::from(b))
+	}
+	fn hex_text(hex: &str) -> Vec<u16> {
+		hex_bytes(hex)
+			.chunks(2)
+			.map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]))
+			.collect()
+	}
+	fn tokens(block: &str) -> Vec<Option<&str>> {
+		CMAP_TOKEN_REGEX
+			.captures_iter(block)
+			.map(|c| c.name("hex").map(|hex| hex.as_str()))
+			.collect()
+	}
+
+	let mut map = HashMap::new();
+
+	for block in data.split("beginbfchar").skip(1) {
+		let block = block.split("endbfchar").next().unwrap_or_default();
+		for pair in tokens(block).chunks_exact(2) {
+			if let [Some(src), Some(dst)] = pair {
+				map.insert(hex_code(src), String::from_utf16_lossy(&hex_text(dst)));
+			}
+		}
+	}
+
+	for block in data.split("beginbfrange").skip(1) {
+		let block = block.split("endbfrange").next().unwrap_or_default();
+		let mut tokens = tokens(block).into_iter();
+
+		while let (Some(Some(lo)), Some(Some(hi))) = (tokens.next(), tokens.next()) {
+			let (lo, hi) = (hex_code(lo), hex_code(hi));
+
+			match tokens.next() {
+				Some(Some(dst)) => {
+					let dst = hex_text(dst);
+					for (offset, code) in (lo..=hi).enumerate() {
+						let mut text = dst.clone();
+						if let Some(last) = text.last_mut() {
+							*last = last.wrapping_add(u16::try_from(offset).unwrap_or(0));
+						}
+						map.insert(code, String::from_utf16_lossy(&text));
+					}
+				}
+				// `[` followed by one destination per code.
+				Some(None) => {
+					for code in lo.. {
+						match tokens.next() {
+							Some(Some(dst)) => {
+								map.insert(code, String::from_utf16_lossy(&hex_text(dst)));
+							}
+							_ => break,
+						}
+					}
+				}
+				None => break,
+			}
+		}
+	}
+
+	map
+}
+
+/// The page's `MediaBox`, as given in the PDF with `y` growing up the page.
+fn media_box(document: &Document, page_id: ObjectId) -> Rect {
+	let mut dict = document.get_dictionary(page_id).ok();
+
+	// MediaBox is inheritable, so walk up the page tree until one turns up.
+	while let Some(page) = dict {
+		if let Some([x0, y0, x1, y1]) = page
+			.get_deref(b"MediaBox", document)
+			.and_then(Object::as_array)
+			.ok()
+			.and_then(|media_box| {
+				let coords: Vec<f32> = media_box
+					.iter()
+					.filter_map(|value| value.as_float().ok())
+					.collect();
+				<[f32; 4]>::try_from(coords).ok()
+			}) {
+			return Rect { x0, y0, x1, y1 };
+		}
+		dict = page
+			.get(b"Parent")
+			.and_then(Object::as_reference)
+			.and_then(|id| document.get_dictionary(id))
+			.ok();
+	}
+
+	Rect {
+		x0: 0.0,
+		y0: 0.0,
+		x1: 612.0,
+		y1: 792.0,
+	}
+}
+
+/// Collects the runs of text a page draws, in the order it draws them, and the rectangles
+/// it paints, both in PDF user space.
+///
+/// Form `XObject`s are drawn where the page invokes them. Text is kept whatever its rendering
+/// mode, like mupdf does, so the invisible text layer of a scanned page comes through.
+fn page_runs(document: &Document, page_id: ObjectId) -> lopdf::Result<(Vec<Run>, Vec<Rect>)> {
+	let fonts = document
+		.get_page_fonts(page_id)
+		.into_iter()
+		.map(|(name, dict)| (name, Font::load(document, dict)))
 		.collect();
+	let (inline, ids) = document.get_page_resources(page_id);
+	let resources = Resources {
+		dicts: inline
+			.into_iter()
+			.chain(
+				ids.into_iter()
+					.filter_map(|id| document.get_dictionary(id).ok()),
+			)
+			.collect(),
+		fonts,
+	};
+	let content = Content::decode(&document.get_page_content(page_id)?)?;
+
+	let mut painter = Painter {
+		document,
+		runs: Vec::new(),
+		boxes: Vec::new(),
+	};
+	painter.paint(&content.operations, &resources, GraphicsState::default(), 0);
+
+	Ok((painter.runs, painter.boxes))
+}
+
+/// How deep Form `XObject`s may nest, so one that draws itself can't recurse forever.
+const MAX_FORM_DEPTH: usize = 8;
+
+/// The fonts and other named resources a content stream can use.
+struct Resources<'a> {
+	dicts: Vec<&'a Dictionary>,
+	fonts: BTreeMap<Vec<u8>, Font>,
+}
+
+impl<'a> Resources<'a> {
+	fn load(document: &'a Document, dict: &'a Dictionary) -> Self {
+		let fonts = dict
+			.get_deref(b"Font", document)
+			.and_then(Object::as_dict)
+			.map(|fonts| {
+				fonts
+					.iter()
+					.filter_map(|(name, font)| {
+						let font = document.dereference(font).ok()?.1.as_dict().ok()?;
+						Some((name.clone(), Font::load(document, font)))
+					})
+					.collect()
+			})
+			.unwrap_or_default();
+
+		Self {
+			dicts: vec![dict],
+			fonts,
+		}
+	}
+
+	fn xobject(&self, document: &'a Document, name: &[u8]) -> Option<&'a Stream> {
+		self.dicts.iter().find_map(|dict| {
+			dict.get_deref(b"XObject", document)
+				.and_then(Object::as_dict)
+				.and_then(|xobjects| xobjects.get_deref(name, document))
+				.and_then(Object::as_stream)
+				.ok()
+		})
+	}
+}
+
+struct Painter<'a> {
+	document: &'a Document,
+	runs: Vec<Run>,
+	boxes: Vec<Rect>,
+}
+
+impl Painter<'_> {
+	fn paint(
+		&mut self,
+		operations: &[Operation],
+		resources: &Resources<'_>,
+		mut state: GraphicsState,
+		depth: usize,
+	) {
+		let mut stack = Vec::new();
+		let mut tm = IDENTITY;
+		let mut tlm = IDENTITY;
+		let mut path = Vec::new();
+
+		for operation in operations {
+			let operands = &operation.operands;
+			let num = |i: usize| {
+				operands
+					.get(i)
+					.and_then(|o| o.as_float().ok())
+					.unwrap_or(0.0)
+			};
+
+			match operation.operator.as_str() {
+				"q" => stack.push(state.clone()),
+				"Q" => state = stack.pop().unwrap_or_default(),
+				"cm" => {
+					let m = [num(0), num(1), num(2), num(3), num(4), num(5)];
+					state.ctm = mul(&m, &state.ctm);
+				}
+				"re" => path.push(transform_rect(&state.ctm, num(0), num(1), num(2), num(3))),
+				"f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "S" | "s" => {
+					self.boxes.append(&mut path);
+				}
+				"n" => path.clear(),
+				"BT" => {
+					tm = IDENTITY;
+					tlm = IDENTITY;
+				}
+				"Tf" => {
+					state.font = operands
+						.first()
+						.and_then(|o| o.as_name().ok())
+						.map(<[u8]>::to_vec);
+					state.font_size = num(1);
+				}
+				"Tc" => state.char_spacing = num(0),
+				"Tw" => state.word_spacing = num(0),
+				"Tz" => state.scale = num(0) / 100.0,
+				"TL" => state.leading = num(0),
+				"Td" | "TD" => {
+					if operation.operator == "TD" {
+						state.leading = -num(1);
+					}
+					tlm = mul(&translate(num(0), num(1)), &tlm);
+					tm = tlm;
+				}
+				"Tm" => {
+					tlm = [num(0), num(1), num(2), num(3), num(4), num(5)];
+					tm = tlm;
+				}
+				"T*" | "'" | "\"" => {
+					if operation.operator == "\"" {
+						state.word_spacing = num(0);
+						state.char_spacing = num(1);
+					}
+					tlm = mul(&translate(0.0, -state.leading), &tlm);
+					tm = tlm;
+
+					if let Some(Object::String(bytes, _)) = operands.last() {
+						let font = state
+							.font
+							.as_ref()
+							.and_then(|name| resources.fonts.get(name));
+						self.runs.extend(show(&state, font, &mut tm, bytes));
+					}
+				}
+				"Tj" | "TJ" => {
+					let font = state
+						.font
+						.as_ref()
+						.and_then(|name| resources.fonts.get(name));
+					let items = match operands.first() {
+						Some(Object::Array(items)) => items.as_slice(),
+						_ => operands.as_slice(),
+					};
+					self.runs.extend(show_array(&state, font, &mut tm, items));
+				}
+				"Do" if depth < MAX_FORM_DEPTH => {
+					if let Some(form) = operands
+						.first()
+						.and_then(|o| o.as_name().ok())
+						.and_then(|name| resources.xobject(self.document, name))
+					{
+						self.paint_form(form, resources, &state, depth);
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	/// Draws a Form `XObject` with the state it was invoked in.
+	fn paint_form(
+		&mut self,
+		form: &Stream,
+		resources: &Resources<'_>,
+		state: &GraphicsState,
+		depth: usize,
+	) {
+		if form.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form") {
+			return;
+		}
+		let Ok(content) = form
+			.decompressed_content()
+			.or_else(|_| Ok(form.content.clone()))
+			.and_then(|data| Content::decode(&data))
+		else {
+			return;
+		};
+
+		let matrix = form
+			.dict
+			.get_deref(b"Matrix", self.document)
+			.and_then(Object::as_array)
+			.ok()
+			.and_then(|m| {
+				let m: Vec<f32> = m.iter().filter_map(|v| v.as_float().ok()).collect();
+				Matrix::try_from(m).ok()
+			})
+			.unwrap_or(IDENTITY);
+		let state = GraphicsState {
+			ctm: mul(&matrix, &state.ctm),
+			..state.clone()
+		};
+
+		// A form without resources of its own uses the ones of what invoked it.
+		match form
+			.dict
+			.get_deref(b"Resources", self.document)
+			.and_then(Object::as_dict)
+		{
+			Ok(dict) => {
+				let own = Resources::load(self.document, dict);
+				self.paint(&content.operations, &own, state, depth + 1);
+			}
+			Err(_) => self.paint(&content.operations, resources, state, depth + 1),
+		}
+	}
+}
+
+/// Turns joined runs into lines, with `y` growing down from `top` like the mupdf backend's.
+fn to_lines(runs: Vec<Run>, top: f32) -> Vec<PdfLine> {
+	runs.into_iter()
+		.map(|mut run| {
+			// Runs only know their baseline, so allow for ascenders and descenders.
+			run.line.bbox = Rect {
+				x0: run.x,
+				y0: top - (run.y + run.size * 0.8),
+				x1: run.end_x,
+				y1: top - (run.y - run.size * 0.2),
+			};
+			run.line
+		})
+		.collect()
+}
+
+/// Shows one string, advancing the text matrix past it.
+fn show(state: &GraphicsState, font: Option<&Font>, tm: &mut Matrix, bytes: &[u8]) -> Option<Run> {
+	let font = font?;
+	let start = mul(tm, &state.ctm);
+	let size = state.font_size * start[2].hypot(start[3]);
+	let style = font.style(size);
+
+	let mut line = PdfLine::default();
+	for (code, chars) in font.decode(bytes) {
+		let word_spacing = if chars == " " {
+			state.word_spacing
+		} else {
+			0.0
+		};
+		let tx = (font.width(code) / 1000.0 * state.font_size + state.char_spacing + word_spacing)
+			* state.scale;
+		*tm = mul(&translate(tx, 0.0), tm);
+		line.push_str(&chars, &style);
+	}
+
+	let end = mul(tm, &state.ctm);
+	Some(Run {
+		x: start[4],
+		y: start[5],
+		end_x: end[4],
+		size,
+		line,
+	})
+}
+
+/// Shows the strings of a `TJ` array, applying the adjustments between them.
+fn show_array(
+	state: &GraphicsState,
+	font: Option<&Font>,
+	tm: &mut Matrix,
+	items: &[Object],
+) -> Vec<Run> {
+	let mut runs = Vec::new();
+
+	for item in items {
+		match item {
+			Object::String(bytes, _) => runs.extend(show(state, font, tm, bytes)),
+			Object::Integer(_) | Object::Real(_) => {
+				let tx = -item.as_float().unwrap_or(0.0) / 1000.0 * state.font_size * state.scale;
+				*tm = mul(&translate(tx, 0.0), tm);
+			}
+			_ => {}
+		}
+	}
+
+	runs
+}
+
+/// Joins runs on the same baseline into lines, the way mupdf does: a small gap becomes a
+/// space, one wider than `max_gap` ems starts a new line.
+fn join_runs(runs: Vec<Run>, max_gap: f32) -> Vec<Run> {
+	let mut lines: Vec<Run> = Vec::new();
+
+	for run in runs {
+		match lines.last_mut() {
+			Some(line)
+				if (line.y - run.y).abs() < line.size * 0.3
+					&& run.x > line.end_x - line.size
+					&& run.x - line.end_x < line.size * max_gap =>
+			{
+				if run.x - line.end_x > line.size * 0.2
+					&& !line.line.text.ends_with(' ')
+					&& !run.line.text.starts_with(' ')
+				{
+					line.line.text.push(' ');
+				}
+				line.line.push_line(&run.line);
+				line.end_x = run.end_x;
+			}
+			_ if run.line.text.trim().is_empty() => {}
+			_ => lines.push(run),
+		}
+	}
+
+	lines
 }
@@ -1,14 +1,164 @@
-// #[cfg(feature = "lopdf")]
-// mod lopdf;
-// #[cfg(feature = "lopdf")]
-// pub use lopdf::extract_pages;
+//! Text extraction backends.
+//!
+//! `mupdf` (the default) links against the system `libmupdf`; `lopdf` is pure Rust. With both
+//! enabled, mupdf is the [`default`] backend, and either can still be passed explicitly.
+//!
+//! `ocr` adds OCR with the system Tesseract to the mupdf backend, for pages without a text
+//! layer and for sections that ask for it.
 
+#[cfg(not(any(feature = "mupdf", feature = "lopdf")))]
+compile_error!("At least one of the `mupdf` and `lopdf` features must be enabled.");
+
+mod boxes;
+mod layout;
+#[cfg(feature = "lopdf")]
+pub mod lopdf;
 #[cfg(feature = "mupdf")]
-mod mupdf;
+pub mod mupdf;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod page;
+mod running;
+mod table;
 
-use std::collections::BTreeMap;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::Path,
+};
 
-#[cfg(feature = "mupdf")]
-pub use mupdf::extract_pages;
+use cofd_meta::{Columns, PageLabel};
+
+pub use self::{
+	page::{join_lines, slice_lines, style_at, FontStyle, PdfLine, PdfPage, Rect, Span},
+	table::Table,
+};
 
+/// The plain text of each page.
 pub type PdfText = BTreeMap<usize, Vec<String>>;
+/// Each page, with the styles and positions of its lines.
+pub type PdfPages = BTreeMap<usize, PdfPage>;
+
+#[must_use]
+pub fn to_text(pages: &PdfPages) -> PdfText {
+	pages
+		.iter()
+		.map(|(i, page)| {
+			(
+				*i,
+				page.lines.iter().map(|line| line.text.clone()).collect(),
+			)
+		})
+		.collect()
+}
+
+/// What a PDF's document information says about it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentInfo {
+	pub title: Option<String>,
+	pub producer: Option<String>,
+}
+
+/// A way of getting laid-out text out of a PDF.
+pub trait TextBackend: Send + Sync {
+	fn open(&self, path: &Path) -> anyhow::Result<Box<dyn TextDocument>>;
+}
+
+/// A PDF opened by a [`TextBackend`].
+pub trait TextDocument {
+	fn page_count(&self) -> usize;
+
+	/// The zero-based page `index`, with indented lines starting with a tab.
+	///
+	/// `columns` replaces the detected column layout.
+	fn extract_page(&self, index: usize, columns: Option<&Columns>) -> anyhow::Result<PdfPage>;
+
+	/// The lines of page `index`'s text layer as the PDF draws them, with no layout, running
+	/// text removal or OCR, which makes them the same whichever features are enabled.
+	fn page_text(&self, index: usize) -> anyhow::Result<Vec<String>>;
+
+	/// Page `index` read with OCR rather than from its text layer, or `None` if this backend
+	/// can't do OCR.
+	fn ocr_page(
+		&self,
+		_index: usize,
+		_columns: Option<&Columns>,
+	) -> Option<anyhow::Result<PdfPage>> {
+		None
+	}
+
+	/// The PDF's title and producer, where it gives them.
+	fn info(&self) -> DocumentInfo {
+		DocumentInfo::default()
+	}
+
+	/// The page labels the PDF itself gives, sorted by `start`, if it gives any.
+	fn page_labels(&self) -> Vec<PageLabel> {
+		Vec::new()
+	}
+
+	/// Page `index` as [`Self::extract_pages`] reads it: with OCR if `ocr` is set and the
+	/// backend can do it, and otherwise from its text layer. A page that can't be read is
+	/// left empty, and a page OCR fails on keeps its text layer, with [`PdfPage::error`]
+	/// saying why.
+	fn read_page(&self, index: usize, columns: Option<&Columns>, ocr: bool) -> PdfPage {
+		let text_layer = || {
+			self.extract_page(index, columns)
+				.unwrap_or_else(|err| PdfPage::unreadable(index, &err))
+		};
+		if !ocr {
+			return text_layer();
+		}
+
+		match self.ocr_page(index, columns) {
+			Some(Ok(page)) => page,
+			Some(Err(err)) => {
+				log::warn!("Keeping the text of page {index}, as OCR failed: {err:#}");
+				let mut page = text_layer();
+				page.error.get_or_insert(format!("OCR failed: {err:#}"));
+				page
+			}
+			None => {
+				log::warn!("Page {index} is to be read with OCR, but the backend can't do OCR");
+				text_layer()
+			}
+		}
+	}
+
+	/// Every page, with the column layout of some of them given in `columns` and the pages
+	/// in `ocr` read with OCR.
+	///
+	/// Unlike [`Self::read_page`], this also drops running headers and footers, which can
+	/// only be told apart from the text by looking at several pages, and numbers the pages
+	/// from the page numbers among them.
+	fn extract_pages(
+		&self,
+		columns: &BTreeMap<usize, Columns>,
+		ocr: &BTreeSet<usize>,
+	) -> anyhow::Result<PdfPages> {
+		let mut pages = (0..self.page_count())
+			.map(|index| {
+				let page = self.read_page(index, columns.get(&index), ocr.contains(&index));
+				(index, page)
+			})
+			.collect();
+		running::number_pages(&mut pages);
+		running::drop_running_text(&mut pages);
+
+		Ok(pages)
+	}
+}
+
+/// The backend used when none is given.
+#[must_use]
+pub fn default() -> &'static dyn TextBackend {
+	#[cfg(feature = "mupdf")]
+	return &mupdf::MuPdf;
+	#[cfg(not(feature = "mupdf"))]
+	return &lopdf::LoPdf;
+}
+
+pub fn extract_pages(path: impl AsRef<Path>) -> anyhow::Result<PdfPages> {
+	default()
+		.open(path.as_ref())?
+		.extract_pages(&BTreeMap::new(), &BTreeSet::new())
+}
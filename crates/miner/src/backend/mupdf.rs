@@ -1,143 +1,196 @@
-use std::{collections::BTreeMap, path::Path, result::Result};
+use std::path::Path;
 
 use anyhow::anyhow;
-use cofd_schema::DOT_CHAR;
-use mupdf::{Document, TextPageOptions};
-use once_cell::sync::Lazy;
-use regex::Regex;
+use cofd_meta::Columns;
+use mupdf::{Document, MetadataName, Page, TextPage, TextPageOptions};
+
+use super::{
+	boxes::{inset_blocks, mark_boxed},
+	layout::layout_page,
+	table::detect_tables,
+	DocumentInfo, FontStyle, PdfLine, PdfPage, Rect, TextBackend, TextDocument,
+};
+
+/// Consecutive chars whose origins are more than this many ems apart are in different table
+/// cells.
+const CELL_GAP: f32 = 2.0;
+
+/// Extracts text with mupdf.
+pub struct MuPdf;
+
+impl TextBackend for MuPdf {
+	fn open(&self, path: &Path) -> anyhow::Result<Box<dyn TextDocument>> {
+		let document = Document::open(
+			path.to_str()
+				.ok_or(anyhow!("Path is not valid utf-8 string"))?,
+		)?;
+
+		Ok(Box::new(MuPdfDocument(document)))
+	}
+}
 
-use super::PdfText;
+struct MuPdfDocument(Document);
 
-static DOT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(&format!("^{DOT_CHAR} ")).unwrap());
+impl TextDocument for MuPdfDocument {
+	fn page_count(&self) -> usize {
+		self.0
+			.page_count()
+			.ok()
+			.and_then(|count| usize::try_from(count).ok())
+			.unwrap_or(0)
+	}
 
-const THRESHOLD: f32 = 240.0;
+	fn extract_page(&self, index: usize, columns: Option<&Columns>) -> anyhow::Result<PdfPage> {
+		let page = self.0.load_page(i32::try_from(index)?)?;
+		let text_page = page.to_text_page(TextPageOptions::empty())?;
+		let (lines, cells, blocks) = text_layer(&text_page);
 
-pub fn extract_pages(path: impl AsRef<Path>) -> anyhow::Result<PdfText> {
-	let document = Document::open(
-		path.as_ref()
-			.to_str()
-			.ok_or(anyhow!("Path is not valid utf-8 string"))?,
-	)?;
-	let mut pages = BTreeMap::new();
+		// Scans have no text layer to read.
+		#[cfg(feature = "ocr")]
+		let lines = if lines.iter().all(|line| line.text.trim().is_empty()) {
+			super::ocr::read_page(&page)?
+		} else {
+			lines
+		};
 
-	for (i, text_page) in document
-		.pages()?
-		.filter_map(Result::ok)
-		.filter_map(|p| p.to_text_page(TextPageOptions::empty()).ok())
-		.enumerate()
-	{
-		let mut l_indent = (f32::MAX, f32::MIN);
-		let mut r_indent = (f32::MAX, f32::MIN);
+		to_page(&page, lines, &cells, &blocks, columns)
+	}
 
-		let mut last_y = 0.0;
-		let mut blank = false;
+	fn page_text(&self, index: usize) -> anyhow::Result<Vec<String>> {
+		let page = self.0.load_page(i32::try_from(index)?)?;
+		let text_page = page.to_text_page(TextPageOptions::empty())?;
+		let (lines, _, _) = text_layer(&text_page);
 
-		let mut lines = Vec::new();
+		Ok(lines.into_iter().map(|line| line.text).collect())
+	}
 
-		for block in text_page.blocks() {
-			for line in block.lines() {
-				let x = line.bounds().x0;
-				let y = line.bounds().y0;
-				let line = line.chars().filter_map(|c| c.char()).collect::<String>();
+	fn info(&self) -> DocumentInfo {
+		let metadata = |name| {
+			self.0
+				.metadata(name)
+				.ok()
+				.filter(|value| !value.trim().is_empty())
+		};
+
+		DocumentInfo {
+			title: metadata(MetadataName::Title),
+			producer: metadata(MetadataName::Producer),
+		}
+	}
 
-				let y_shift = (y - last_y).floor();
+	#[cfg(feature = "ocr")]
+	fn ocr_page(&self, index: usize, columns: Option<&Columns>) -> Option<anyhow::Result<PdfPage>> {
+		Some(self.ocr(index, columns))
+	}
+}
 
-				if y_shift.abs() > 100.0 {
-					blank = y_shift > 0.0; // End of Page Content
-				}
-				last_y = y;
+#[cfg(feature = "ocr")]
+impl MuPdfDocument {
+	fn ocr(&self, index: usize, columns: Option<&Columns>) -> anyhow::Result<PdfPage> {
+		let page = self.0.load_page(i32::try_from(index)?)?;
+		let lines = super::ocr::read_page(&page)?;
+
+		to_page(&page, lines, &[], &[], columns)
+	}
+}
 
-				if blank || line.trim().chars().all(char::is_numeric) {
-					// Some(format!(
-					// 	"{min_x}{indent}:BLANK:{}",
-					// 	l.chars().filter_map(|c| c.char()).collect::<String>()
-					// ));
+/// The lines of the page's text layer, the cells they split into and the blocks they're in,
+/// with the number of lines in each.
+fn text_layer(text_page: &TextPage) -> (Vec<PdfLine>, Vec<PdfLine>, Vec<(Rect, usize)>) {
+	let mut lines = Vec::new();
+	let mut cells = Vec::new();
+	let mut blocks = Vec::new();
+	for block in text_page.blocks() {
+		let bounds = block.bounds();
+		blocks.push((
+			Rect {
+				x0: bounds.x0,
+				y0: bounds.y0,
+				x1: bounds.x1,
+				y1: bounds.y1,
+			},
+			block.lines().count(),
+		));
+
+		for line in block.lines() {
+			let bounds = line.bounds();
+			let bbox = Rect {
+				x0: bounds.x0,
+				y0: bounds.y0,
+				x1: bounds.x1,
+				y1: bounds.y1,
+			};
+
+			let mut text = PdfLine {
+				bbox,
+				..PdfLine::default()
+			};
+			let mut cell = text.clone();
+			let mut last_x = None;
+			for c in line.chars() {
+				let Some(char) = c.char() else {
 					continue;
+				};
+				let style = FontStyle::from_name(
+					c.font().as_ref().map_or("", |font| font.name()),
+					c.size(),
+				);
+				let x = c.origin().x;
+
+				if last_x.is_some_and(|last_x| x - last_x > c.size() * CELL_GAP) {
+					cells.push(std::mem::replace(
+						&mut cell,
+						PdfLine {
+							bbox,
+							..PdfLine::default()
+						},
+					));
 				}
-
-				if x > THRESHOLD {
-					if x < r_indent.0 {
-						r_indent.0 = x;
-					}
-				} else if x < l_indent.0 {
-					l_indent.0 = x;
+				if cell.text.is_empty() {
+					cell.bbox.x0 = x;
 				}
+				cell.bbox.x1 = x + c.size() / 2.0;
+				last_x = Some(x);
 
-				lines.push((x, line));
+				let mut buf = [0; 4];
+				let char = char.encode_utf8(&mut buf);
+				text.push_str(char, &style);
+				cell.push_str(char, &style);
 			}
-		}
-
-		let mut last_x = 0.0;
-		let mut last_indent = 0.0;
 
-		let mut last_has_dot = false;
-		// let mut dot_line_indent = f32::MAX;
-		// let mut dot_paragraph_indent = f32::MAX;
-		// let mut pre_dot_indent = f32::MAX;
-
-		let mut last_should_tab = false;
-		let mut last_line = String::new();
-
-		let lines = lines
-			.into_iter()
-			.map(|(x, line)| {
-				let min_x = if x < THRESHOLD {
-					l_indent.0
-				} else {
-					r_indent.0
-				};
-
-				let dot = DOT_REGEX.is_match(&line);
-				let indent = (x - min_x).floor();
-				let indent = if dot { indent.max(9.0) } else { indent };
-
-				#[allow(clippy::if_same_then_else, clippy::nonminimal_bool)]
-				let should_tab = if indent > last_indent {
-					if last_has_dot && !dot {
-						false
-					} else {
-						true
-					}
-				} else if indent < last_indent {
-					if dot {
-						true
-					} else {
-						false
-					}
-				} else if last_line.trim().ends_with(':') && dot {
-					true
-				} else {
-					last_should_tab
-				};
-
-				let indent = if indent == 0.0 && should_tab {
-					9.0
-				} else {
-					indent
-				};
-
-				last_x = x;
-
-				last_indent = indent;
-				last_should_tab = should_tab;
-				last_has_dot = dot;
-				last_line.clone_from(&line);
-
-				let prefix = if should_tab { "\t" } else { "" };
-
-				#[cfg(debug_assertions)]
-				if std::env::var("INDENT_DEBUG").is_ok() {
-					format!("{indent}{prefix}{line}")
-				} else {
-					format!("{prefix}{line}")
-				}
-				#[cfg(not(debug_assertions))]
-				format!("{prefix}{line}")
-			})
-			.collect();
-
-		pages.insert(i, lines);
+			cells.push(cell);
+			lines.push(text);
+		}
 	}
 
-	Ok(pages)
+	(lines, cells, blocks)
+}
+
+/// Lays out the lines of `page` and finds its boxes and tables.
+fn to_page(
+	page: &Page,
+	lines: Vec<PdfLine>,
+	cells: &[PdfLine],
+	blocks: &[(Rect, usize)],
+	columns: Option<&Columns>,
+) -> anyhow::Result<PdfPage> {
+	let page_bounds = page.bounds()?;
+	let (width, height) = (
+		page_bounds.x1 - page_bounds.x0,
+		page_bounds.y1 - page_bounds.y0,
+	);
+	// mupdf doesn't give the page's drawings, so boxes are found from how the blocks of
+	// text sit in their columns.
+	let mut lines = layout_page(lines, columns);
+	let boxes = inset_blocks(blocks, &lines);
+	mark_boxed(&mut lines, &boxes, width, height);
+
+	Ok(PdfPage {
+		width,
+		height,
+		lines,
+		tables: detect_tables(cells),
+		number: None,
+		error: None,
+	})
 }
@@ -0,0 +1,93 @@
+use mupdf::{Colorspace, Matrix, Page};
+use tesseract::Tesseract;
+
+use super::{FontStyle, PdfLine, Rect};
+
+/// The resolution pages are rendered at to be read.
+const DPI: u16 = 300;
+const LANGUAGE: &str = "eng";
+
+/// Renders `page` and reads its lines with Tesseract, in points like the text layer's.
+pub fn read_page(page: &Page) -> anyhow::Result<Vec<PdfLine>> {
+	let scale = f32::from(DPI) / 72.0;
+	let pixmap = page.to_pixmap(
+		&Matrix::new_scale(scale, scale),
+		&Colorspace::device_gray(),
+		0.0,
+		false,
+	)?;
+
+	let mut tesseract = Tesseract::new(None, Some(LANGUAGE))?
+		.set_frame(
+			pixmap.samples(),
+			i32::try_from(pixmap.width())?,
+			i32::try_from(pixmap.height())?,
+			i32::from(pixmap.n()),
+			i32::try_from(pixmap.stride())?,
+		)?
+		.set_source_resolution(i32::from(DPI))
+		.recognize()?;
+
+	Ok(tsv_lines(&tesseract.get_tsv_text(0)?, scale))
+}
+
+/// Tesseract's block, paragraph and line numbers for a word.
+type LineKey<'a> = (&'a str, &'a str, &'a str);
+
+/// Joins the words of Tesseract's TSV output into lines. Each row is `level page block
+/// paragraph line word left top width height confidence text`, with words at level 5.
+fn tsv_lines(tsv: &str, scale: f32) -> Vec<PdfLine> {
+	let mut lines: Vec<(LineKey, PdfLine, Vec<f32>)> = Vec::new();
+
+	for row in tsv.lines() {
+		let Ok([level, _, block, paragraph, line, _, left, top, width, height, confidence, text]) =
+			<[&str; 12]>::try_from(row.splitn(12, '\t').collect::<Vec<_>>())
+		else {
+			continue;
+		};
+		if level != "5" || text.trim().is_empty() {
+			continue;
+		}
+
+		let number = |cell: &str| cell.parse::<f32>().unwrap_or(0.0);
+		let bbox = Rect {
+			x0: number(left) / scale,
+			y0: number(top) / scale,
+			x1: (number(left) + number(width)) / scale,
+			y1: (number(top) + number(height)) / scale,
+		};
+		// OCR can't tell the font, but the size helps with the layout.
+		let style = FontStyle {
+			size: bbox.y1 - bbox.y0,
+			..FontStyle::default()
+		};
+		let key = (block, paragraph, line);
+
+		match lines.last_mut() {
+			Some((last, line, confidences)) if *last == key => {
+				line.push_str(" ", &style);
+				line.push_str(text, &style);
+				line.bbox = line.bbox.union(bbox);
+				confidences.push(number(confidence));
+			}
+			_ => {
+				let mut line = PdfLine {
+					bbox,
+					..PdfLine::default()
+				};
+				line.push_str(text, &style);
+				lines.push((key, line, vec![number(confidence)]));
+			}
+		}
+	}
+
+	lines
+		.into_iter()
+		.map(|(_, mut line, confidences)| {
+			#[allow(clippy::cast_precision_loss)]
+			let mean = confidences.iter().sum::<f32>() / confidences.len() as f32;
+			line.confidence = Some(mean / 100.0);
+			line
+		})
+		.collect()
+}
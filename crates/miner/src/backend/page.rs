@@ -0,0 +1,323 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::Table;
+
+/// How a run of text is set.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontStyle {
+	pub size: f32,
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub bold: bool,
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub italic: bool,
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub small_caps: bool,
+}
+
+impl FontStyle {
+	/// Guesses the style from a font name like `MinionPro-BoldIt` or `ABCDEF+Goudy-SC`.
+	#[must_use]
+	pub fn from_name(name: &str, size: f32) -> Self {
+		// Drop the subset tag of embedded fonts.
+		let name = name.split_once('+').map_or(name, |(_, name)| name);
+		let style = name
+			.rsplit_once(['-', ','])
+			.map_or("", |(_, style)| style)
+			.to_lowercase();
+		let name = name.to_lowercase();
+
+		Self {
+			size,
+			bold: ["bold", "black", "heavy", "semibold", "demi"]
+				.iter()
+				.any(|weight| name.contains(weight)),
+			italic: name.contains("italic") || name.contains("oblique") || style.ends_with("it"),
+			small_caps: name.contains("smallcaps") || style.ends_with("sc"),
+		}
+	}
+}
+
+/// A rectangle on a page, in points, with `y` growing down the page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+	pub x0: f32,
+	pub y0: f32,
+	pub x1: f32,
+	pub y1: f32,
+}
+
+impl Rect {
+	/// The smallest rectangle containing both.
+	#[must_use]
+	pub fn union(self, other: Self) -> Self {
+		Self {
+			x0: self.x0.min(other.x0),
+			y0: self.y0.min(other.y0),
+			x1: self.x1.max(other.x1),
+			y1: self.y1.max(other.y1),
+		}
+	}
+}
+
+/// A page of text, laid out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PdfPage {
+	pub width: f32,
+	pub height: f32,
+	pub lines: Vec<PdfLine>,
+	/// Tables found from how the text on the page lines up. Their text is in `lines` too.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tables: Vec<Table>,
+	/// The number printed on the page, if one was found in its margins.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub number: Option<usize>,
+	/// What went wrong reading the page, if anything. An unreadable page has no lines.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+impl PdfPage {
+	/// A page that couldn't be read, so the rest of the book can still be.
+	#[must_use]
+	pub fn unreadable(index: usize, err: &anyhow::Error) -> Self {
+		log::warn!("Skipping page {index}: {err:#}");
+
+		Self {
+			error: Some(format!("{err:#}")),
+			..Self::default()
+		}
+	}
+}
+
+/// A byte range of a line's text set in one style.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+	pub range: Range<usize>,
+	pub style: FontStyle,
+}
+
+/// One line of a page as the parsers see it, with indented lines starting with a tab.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PdfLine {
+	pub text: String,
+	/// Where the line is on its page.
+	#[serde(default)]
+	pub bbox: Rect,
+	/// Which column of its page the line is in, counting from the left.
+	#[serde(default)]
+	pub column: usize,
+	/// The styled runs of `text`, in order. The leading tab isn't covered.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub spans: Vec<Span>,
+	/// Whether the line is inside a box or sidebar, set apart from the running text.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub boxed: bool,
+	/// How sure OCR was of the line, from 0 to 1. `None` for lines from the text layer.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub confidence: Option<f32>,
+}
+
+impl PdfLine {
+	/// A line with no text at the same place as this one.
+	pub(crate) fn empty_like(&self) -> Self {
+		Self {
+			text: String::new(),
+			bbox: self.bbox,
+			column: self.column,
+			spans: Vec::new(),
+			boxed: self.boxed,
+			confidence: self.confidence,
+		}
+	}
+
+	/// The style most of the line is set in.
+	#[must_use]
+	pub fn font(&self) -> Option<&FontStyle> {
+		self.spans
+			.iter()
+			.max_by_key(|span| span.range.len())
+			.map(|span| &span.style)
+	}
+
+	/// Appends `text` set in `style`, extending the last span if it has the same style.
+	pub fn push_str(&mut self, text: &str, style: &FontStyle) {
+		let start = self.text.len();
+		self.text.push_str(text);
+		let end = self.text.len();
+
+		match self.spans.last_mut() {
+			Some(span) if span.range.end == start && span.style == *style => span.range.end = end,
+			_ => self.spans.push(Span {
+				range: start..end,
+				style: style.clone(),
+			}),
+		}
+	}
+
+	/// Appends `other`, merging its first span into the last one if they have the same style.
+	pub fn push_line(&mut self, other: &Self) {
+		let mut last = 0;
+		for span in &other.spans {
+			self.text.push_str(&other.text[last..span.range.start]);
+			self.push_str(&other.text[span.range.clone()], &span.style);
+			last = span.range.end;
+		}
+		self.text.push_str(&other.text[last..]);
+	}
+
+	/// Prepends `prefix`, which gets no style.
+	#[must_use]
+	pub fn prefixed(mut self, prefix: &str) -> Self {
+		self.text.insert_str(0, prefix);
+		for span in &mut self.spans {
+			span.range = span.range.start + prefix.len()..span.range.end + prefix.len();
+		}
+		self
+	}
+
+	/// The style at byte `pos` of `text`.
+	#[must_use]
+	pub fn style_at(&self, pos: usize) -> Option<&FontStyle> {
+		style_at(&self.spans, pos)
+	}
+
+	/// Rebuilds the line char by char, keeping spans on the chars they covered.
+	#[must_use]
+	pub fn map_chars(&self, mut f: impl FnMut(char, Option<&FontStyle>) -> String) -> Self {
+		let mut line = self.empty_like();
+
+		for (pos, char) in self.text.char_indices() {
+			let style = self.style_at(pos);
+			let text = f(char, style);
+			match style {
+				Some(style) => line.push_str(&text, style),
+				None => line.text.push_str(&text),
+			}
+		}
+
+		line
+	}
+
+	/// The part of the line in byte `range`. The bounding box stays that of the whole line.
+	#[must_use]
+	pub fn slice(&self, range: Range<usize>) -> Self {
+		Self {
+			text: self.text[range.clone()].to_owned(),
+			bbox: self.bbox,
+			column: self.column,
+			spans: self
+				.spans
+				.iter()
+				.filter(|span| span.range.start < range.end && span.range.end > range.start)
+				.map(|span| Span {
+					range: span.range.start.max(range.start) - range.start
+						..span.range.end.min(range.end) - range.start,
+					style: span.style.clone(),
+				})
+				.collect(),
+			boxed: self.boxed,
+			confidence: self.confidence,
+		}
+	}
+
+	/// Wraps italic runs in `*`, as Markdown emphasis.
+	#[must_use]
+	pub fn with_emphasis(&self) -> Self {
+		let mut line = self.empty_like();
+		let mut last = 0;
+
+		for span in &self.spans {
+			line.text.push_str(&self.text[last..span.range.start]);
+
+			let text = &self.text[span.range.clone()];
+			let trimmed = text.trim();
+			if span.style.italic && !trimmed.is_empty() {
+				let lead = &text[..text.len() - text.trim_start().len()];
+				let trail = &text[text.trim_end().len()..];
+
+				line.text.push_str(lead);
+				line.push_str(&format!("*{trimmed}*"), &span.style);
+				line.text.push_str(trail);
+			} else {
+				line.push_str(text, &span.style);
+			}
+			last = span.range.end;
+		}
+		line.text.push_str(&self.text[last..]);
+
+		line
+	}
+}
+
+impl From<String> for PdfLine {
+	fn from(text: String) -> Self {
+		Self {
+			text,
+			..Self::default()
+		}
+	}
+}
+
+impl From<&str> for PdfLine {
+	fn from(text: &str) -> Self {
+		Self::from(text.to_owned())
+	}
+}
+
+/// Joins lines with `\n`, moving their spans to offsets in the joined text.
+#[must_use]
+pub fn join_lines<'a>(lines: impl IntoIterator<Item = &'a PdfLine>) -> (String, Vec<Span>) {
+	let mut text = String::new();
+	let mut spans = Vec::new();
+
+	for (i, line) in lines.into_iter().enumerate() {
+		if i > 0 {
+			text.push('\n');
+		}
+		let offset = text.len();
+		text.push_str(&line.text);
+		spans.extend(line.spans.iter().map(|span| Span {
+			range: span.range.start + offset..span.range.end + offset,
+			style: span.style.clone(),
+		}));
+	}
+
+	(text, spans)
+}
+
+/// The lines covering byte `range` of the text [`join_lines`] would make of `lines`, with
+/// the index of the line each was cut from.
+#[must_use]
+pub fn slice_lines<'a>(
+	lines: impl IntoIterator<Item = &'a PdfLine>,
+	range: Range<usize>,
+) -> Vec<(usize, PdfLine)> {
+	let mut out = Vec::new();
+	let mut start = 0;
+
+	for (i, line) in lines.into_iter().enumerate() {
+		let end = start + line.text.len();
+		if start <= range.end && end >= range.start {
+			out.push((
+				i,
+				line.slice(range.start.max(start) - start..range.end.min(end) - start),
+			));
+		}
+		start = end + 1;
+	}
+
+	out
+}
+
+/// The style at byte `pos`, given spans sorted by position.
+#[must_use]
+pub fn style_at(spans: &[Span], pos: usize) -> Option<&FontStyle> {
+	let i = spans.partition_point(|span| span.range.end <= pos);
+	spans
+		.get(i)
+		.filter(|span| span.range.contains(&pos))
+		.map(|span| &span.style)
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use super::{PdfLine, PdfPages};
+
+/// How much of the top and bottom of a page running headers and footers are looked for in.
+const MARGIN: f32 = 0.12;
+/// How many pages a line must be repeated on to be running text.
+const MIN_REPEATS: usize = 3;
+/// How far apart, in points, the same line may be on different pages.
+const TOLERANCE: f32 = 2.0;
+/// How long a running header or footer may be, like `112 Chapter Three: Laws of the Kill`.
+const MAX_RUNNING_LEN: usize = 60;
+
+/// Sets [`super::PdfPage::number`] from the page numbers printed in the pages' margins.
+pub fn number_pages(pages: &mut PdfPages) {
+	let folios = folios(pages);
+	for (i, page) in pages.iter_mut() {
+		page.number = folios.get(i).map(|(_, number)| *number);
+	}
+}
+
+/// Drops running headers and footers from the margins at the top and bottom of the pages.
+///
+/// Those are short lines that either hold the page number or turn up at the same place,
+/// left edge and height, on several pages, like the chapter's title. Digits don't count
+/// when comparing lines, so a title with the page number in it is still the same title on
+/// every page. Body text repeated at the same height but elsewhere on the page is kept.
+pub fn drop_running_text(pages: &mut PdfPages) {
+	let folios = folios(pages);
+
+	let mut seen: HashMap<String, Vec<(usize, f32, f32)>> = HashMap::new();
+	for (i, page) in pages.iter() {
+		for line in &page.lines {
+			if is_marginal(line, page.height) {
+				seen.entry(key(&line.text))
+					.or_default()
+					.push((*i, line.bbox.x0, line.bbox.y0));
+			}
+		}
+	}
+
+	let is_title = |line: &PdfLine| {
+		seen.get(&key(&line.text)).is_some_and(|found| {
+			let mut pages: Vec<usize> = found
+				.iter()
+				.filter(|(_, x, y)| {
+					(x - line.bbox.x0).abs() <= TOLERANCE && (y - line.bbox.y0).abs() <= TOLERANCE
+				})
+				.map(|(page, _, _)| *page)
+				.collect();
+			pages.dedup();
+			pages.len() >= MIN_REPEATS
+		})
+	};
+
+	for (i, page) in pages.iter_mut() {
+		let height = page.height;
+		let folio = folios.get(i).map(|(line, _)| *line);
+		let mut index = 0..;
+		page.lines.retain(|line| {
+			let is_folio = index.next() == folio;
+			!(is_folio || (is_marginal(line, height) && is_title(line)))
+		});
+	}
+}
+
+/// The page number line of each page that has one, as the line's index and the number.
+///
+/// A number at the start or end of a short margin line is taken for the page number when
+/// it's as far from the page's index, at the same height, on several pages.
+fn folios(pages: &PdfPages) -> HashMap<usize, (usize, usize)> {
+	let mut found: Vec<(usize, usize, isize, f32)> = Vec::new();
+	for (i, page) in pages {
+		for (j, line) in page.lines.iter().enumerate() {
+			if is_marginal(line, page.height)
+				&& let Some(number) = folio(line)
+			{
+				found.push((*i, j, offset(*i, number), line.bbox.y0));
+			}
+		}
+	}
+
+	let is_number = |offset: isize, y: f32| {
+		let mut pages: Vec<usize> = found
+			.iter()
+			.filter(|(_, _, other, other_y)| *other == offset && (other_y - y).abs() <= TOLERANCE)
+			.map(|(page, _, _, _)| *page)
+			.collect();
+		pages.dedup();
+		pages.len() >= MIN_REPEATS
+	};
+
+	let mut folios = HashMap::new();
+	for (page, line, offset, y) in &found {
+		if !folios.contains_key(page)
+			&& is_number(*offset, *y)
+			&& let Some(number) = page.checked_add_signed(*offset)
+		{
+			folios.insert(*page, (*line, number));
+		}
+	}
+
+	folios
+}
+
+/// The number at the start or end of a line, if there's one.
+fn folio(line: &PdfLine) -> Option<usize> {
+	let text = line.text.trim();
+	let mut words = text.split_whitespace();
+	let first = words.next()?;
+	let last = words.next_back().unwrap_or(first);
+
+	[first, last]
+		.into_iter()
+		.find(|word| word.len() <= 4 && word.chars().all(|c| c.is_ascii_digit()))
+		.and_then(|word| word.parse().ok())
+}
+
+/// How far the printed number is from the page's index. Both are well below `isize::MAX`.
+#[allow(clippy::cast_possible_wrap)]
+fn offset(index: usize, number: usize) -> isize {
+	number as isize - index as isize
+}
+
+/// Whether `line` is short and near the top or bottom of its page.
+fn is_marginal(line: &PdfLine, height: f32) -> bool {
+	line.text.trim().len() <= MAX_RUNNING_LEN
+		&& height > 0.0
+		&& (line.bbox.y1 < height * MARGIN || line.bbox.y0 > height * (1.0 - MARGIN))
+}
+
+/// The line with digits and case ignored.
+fn key(text: &str) -> String {
+	text.trim()
+		.chars()
+		.map(|c| if c.is_ascii_digit() { '#' } else { c })
+		.collect::<String>()
+		.to_lowercase()
+}
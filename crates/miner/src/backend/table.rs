@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+use super::{PdfLine, Rect};
+
+/// The fewest columns a row needs to start a table, so two columns of prose don't.
+const MIN_TABLE_COLUMNS: usize = 3;
+/// How many body rows a table needs under its header.
+const MIN_TABLE_ROWS: usize = 2;
+/// A gap down the page of this many row heights ends a table.
+const MAX_ROW_GAP: f32 = 2.5;
+/// How far left of its column's header a cell may start, in points.
+const CELL_SLACK: f32 = 4.0;
+/// How far right of the last header a cell may start and still be part of the table.
+const MAX_CELL_OFFSET: f32 = 40.0;
+
+/// A table found on a page, with its cells as text.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+	pub bbox: Rect,
+	pub header: Vec<String>,
+	/// The body rows, each with one cell per header column.
+	pub rows: Vec<Vec<String>>,
+	/// The row that ended the table by putting two cells in one column, if one did. It's
+	/// left out of `rows`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cut_off: Option<String>,
+}
+
+impl Table {
+	/// The index of the first column whose header starts with one of `names`, ignoring case.
+	#[must_use]
+	pub fn column(&self, names: &[&str]) -> Option<usize> {
+		self.header.iter().position(|header| {
+			let header = header.to_lowercase();
+			names.iter().any(|name| header.starts_with(name))
+		})
+	}
+}
+
+/// Finds tables among `cells`, the pieces of text of a page split wherever there's a gap
+/// wider than a space.
+///
+/// A table starts at a row of at least three cells, which becomes its header, and goes on
+/// while the rows below it line up with the header's columns. A row with only a first cell
+/// continues the name in the row above it. A row with two cells in one column ends the
+/// table, and is kept as its [`Table::cut_off`].
+pub fn detect_tables(cells: &[PdfLine]) -> Vec<Table> {
+	let rows = group_rows(cells);
+
+	let mut tables = Vec::new();
+	let mut i = 0;
+	while i < rows.len() {
+		match read_table(&rows[i..]) {
+			Some((table, used)) => {
+				tables.push(table);
+				i += used;
+			}
+			None => i += 1,
+		}
+	}
+
+	tables
+}
+
+/// Groups cells sharing a baseline, top to bottom, each row left to right.
+fn group_rows(cells: &[PdfLine]) -> Vec<Vec<&PdfLine>> {
+	let mut cells: Vec<&PdfLine> = cells
+		.iter()
+		.filter(|cell| !cell.text.trim().is_empty())
+		.collect();
+	cells.sort_by(|a, b| a.bbox.y1.total_cmp(&b.bbox.y1));
+
+	let mut rows: Vec<Vec<&PdfLine>> = Vec::new();
+	for cell in cells {
+		match rows.last_mut() {
+			Some(row) if (row[0].bbox.y1 - cell.bbox.y1).abs() < row_height(row) / 2.0 => {
+				row.push(cell);
+			}
+			_ => rows.push(vec![cell]),
+		}
+	}
+	for row in &mut rows {
+		row.sort_by(|a, b| a.bbox.x0.total_cmp(&b.bbox.x0));
+	}
+
+	rows
+}
+
+fn row_height(row: &[&PdfLine]) -> f32 {
+	row.iter()
+		.map(|cell| cell.bbox.y1 - cell.bbox.y0)
+		.fold(0.0, f32::max)
+}
+
+/// Reads the table whose header is `rows[0]`, if it is one, with the number of rows used.
+fn read_table(rows: &[Vec<&PdfLine>]) -> Option<(Table, usize)> {
+	let header = rows.first()?;
+	if header.len() < MIN_TABLE_COLUMNS {
+		return None;
+	}
+
+	let starts: Vec<f32> = header
+		.iter()
+		.map(|cell| cell.bbox.x0 - CELL_SLACK)
+		.collect();
+	let right = header.last()?.bbox.x1 + MAX_CELL_OFFSET;
+	let column = |cell: &PdfLine| {
+		(cell.bbox.x0 < right)
+			.then(|| starts.iter().rposition(|start| cell.bbox.x0 >= *start))
+			.flatten()
+	};
+
+	let mut bbox = header.iter().map(|cell| cell.bbox).reduce(Rect::union)?;
+	let mut table = Table {
+		header: header
+			.iter()
+			.map(|cell| cell.text.trim().to_owned())
+			.collect(),
+		..Table::default()
+	};
+	let mut used = 1;
+
+	for row in &rows[1..] {
+		let last_y = bbox.y1;
+		if row[0].bbox.y0 - last_y > row_height(row) * MAX_ROW_GAP {
+			break;
+		}
+
+		let mut cells = vec![String::new(); starts.len()];
+		let mut row_bbox: Option<Rect> = None;
+		let mut last_column = None;
+		for cell in row {
+			let Some(i) = column(cell) else {
+				continue;
+			};
+			if last_column.is_some_and(|last| last >= i) {
+				let text: Vec<&str> = row.iter().map(|cell| cell.text.trim()).collect();
+				table.cut_off = Some(text.join(" "));
+				return finish(table, bbox, used);
+			}
+			last_column = Some(i);
+			cell.text.trim().clone_into(&mut cells[i]);
+			row_bbox = Some(row_bbox.map_or(cell.bbox, |bbox| bbox.union(cell.bbox)));
+		}
+
+		let filled = cells.iter().filter(|cell| !cell.is_empty()).count();
+		match (table.rows.last_mut(), filled, row_bbox) {
+			// A name too long for its cell, wrapped onto the next line.
+			(Some(last), 1, Some(row_bbox)) if !cells[0].is_empty() => {
+				last[0] = format!("{} {}", last[0], cells[0]);
+				bbox = bbox.union(row_bbox);
+			}
+			(_, 2.., Some(row_bbox)) => {
+				table.rows.push(cells);
+				bbox = bbox.union(row_bbox);
+			}
+			_ => break,
+		}
+		used += 1;
+	}
+
+	finish(table, bbox, used)
+}
+
+fn finish(mut table: Table, bbox: Rect, used: usize) -> Option<(Table, usize)> {
+	if table.rows.len() < MIN_TABLE_ROWS {
+		return None;
+	}
+	table.bbox = bbox;
+
+	Some((table, used))
+}
@@ -4,4 +4,10 @@ use thiserror::Error;
 pub enum CofDMinerError {
 	#[error("No such metadata definition found")]
 	NoSuchMeta,
+	#[error("Section \"{section}\": op #{index} is invalid: {reason}")]
+	InvalidOp {
+		section: String,
+		index: usize,
+		reason: String,
+	},
 }
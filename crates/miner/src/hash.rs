@@ -1,4 +1,5 @@
 use std::{
+	collections::{BTreeSet, HashMap, HashSet},
 	fs::File,
 	hash::Hasher,
 	io::{BufReader, Read},
@@ -9,6 +10,13 @@ use anyhow::Result;
 // use fasthash::XXHasher;
 use highway::HighwayHasher;
 
+use crate::backend::{self, TextDocument};
+
+/// How many pages, spread evenly through the book, its fingerprint is taken from.
+const FINGERPRINT_PAGES: usize = 8;
+/// How many pages from the start of a book its title and credits pages are looked for in.
+pub const FRONT_PAGES: usize = 6;
+
 pub fn hash_file(file: &File) -> Result<u64> {
 	let mut reader = BufReader::new(file);
 
@@ -32,3 +40,121 @@ pub fn hash(path: impl AsRef<Path>) -> Result<u64> {
 
 	hash_file(&file)
 }
+
+/// A hash of the book's text, which unlike [`hash`] is the same for copies that differ only
+/// in watermarks, document metadata or incremental updates.
+///
+/// It's taken from the raw text of the pages, [`TextDocument::page_text`], so layout and OCR
+/// don't change it, and every line goes through [`key`], which is frozen: the fingerprints
+/// recorded in the metas only stay valid as long as neither changes.
+pub fn fingerprint(path: impl AsRef<Path>) -> Result<u64> {
+	let document = backend::default().open(path.as_ref())?;
+
+	fingerprint_document(document.as_ref())
+}
+
+/// The fingerprint of a PDF opened by any backend.
+///
+/// Only the [`key`]s of a fixed set of pages count, and a line found on every one of them is
+/// taken for a watermark, like a buyer's name and order number, and left out.
+pub fn fingerprint_document(document: &dyn TextDocument) -> Result<u64> {
+	let count = document.page_count();
+	// The covers are left out, as the pages a storefront is likeliest to stamp or swap.
+	let indices: BTreeSet<usize> = (1..=FINGERPRINT_PAGES)
+		.map(|i| i * count / (FINGERPRINT_PAGES + 1))
+		.collect();
+	let mut pages = page_keys(document, indices)?;
+	let repeats = pages.len();
+	drop_repeated(&mut pages, repeats);
+
+	let mut hasher = HighwayHasher::default();
+	hasher.write_u64(u64::try_from(count)?);
+	for page in &pages {
+		hash_lines(&mut hasher, page);
+		hasher.write_u8(0);
+	}
+
+	Ok(hasher.finish())
+}
+
+/// Fingerprints of each of the first [`FRONT_PAGES`] pages, where the title and credits
+/// pages are. A line on more than half of them is left out, like a watermark.
+pub fn front_page_fingerprints(document: &dyn TextDocument) -> Result<Vec<u64>> {
+	let mut pages = page_keys(document, 0..document.page_count().min(FRONT_PAGES))?;
+	let repeats = pages.len() / 2 + 1;
+	drop_repeated(&mut pages, repeats);
+
+	Ok(pages
+		.iter()
+		.filter(|page| !page.is_empty())
+		.map(|page| {
+			let mut hasher = HighwayHasher::default();
+			hash_lines(&mut hasher, page);
+			hasher.finish()
+		})
+		.collect())
+}
+
+/// The [`key`]s of the non-empty lines of each page's raw text.
+fn page_keys(
+	document: &dyn TextDocument,
+	indices: impl IntoIterator<Item = usize>,
+) -> Result<Vec<Vec<String>>> {
+	indices
+		.into_iter()
+		.map(|index| {
+			Ok(document
+				.page_text(index)?
+				.iter()
+				.map(|line| key(line))
+				.filter(|key| !key.is_empty())
+				.collect())
+		})
+		.collect()
+}
+
+/// Drops the lines found on at least `repeats` of the pages, if that's more than one.
+fn drop_repeated(pages: &mut [Vec<String>], repeats: usize) {
+	if repeats < 2 {
+		return;
+	}
+
+	let mut counts: HashMap<String, usize> = HashMap::new();
+	for page in pages.iter() {
+		for line in page.iter().collect::<HashSet<_>>() {
+			*counts.entry(line.clone()).or_default() += 1;
+		}
+	}
+	for page in pages {
+		page.retain(|line| counts[line] < repeats);
+	}
+}
+
+fn hash_lines(hasher: &mut HighwayHasher, lines: &[String]) {
+	for line in lines {
+		hasher.write(line.as_bytes());
+		hasher.write_u8(b'\n');
+	}
+}
+
+/// The line's ASCII letters and digits, lowercased, with the Latin ligatures spelled out.
+/// Everything else is dropped, so the key doesn't depend on how a backend spaces or
+/// punctuates the line, nor on any Unicode tables.
+///
+/// This is frozen: changing it changes every fingerprint.
+fn key(text: &str) -> String {
+	let mut key = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'\u{FB00}' => key.push_str("ff"),
+			'\u{FB01}' => key.push_str("fi"),
+			'\u{FB02}' => key.push_str("fl"),
+			'\u{FB03}' => key.push_str("ffi"),
+			'\u{FB04}' => key.push_str("ffl"),
+			'\u{FB05}' | '\u{FB06}' => key.push_str("st"),
+			c if c.is_ascii_alphanumeric() => key.push(c.to_ascii_lowercase()),
+			_ => {}
+		}
+	}
+	key
+}
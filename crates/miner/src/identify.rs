@@ -0,0 +1,238 @@
+//! Guessing which book a PDF is when no meta's hash matches it.
+
+use std::{borrow::Cow, collections::HashSet, path::Path};
+
+use anyhow::Result;
+use cofd_meta::{Identity, SectionMeta, SectionRange, SourceMeta};
+
+use crate::{
+	backend::{self, TextBackend, TextDocument},
+	hash::front_page_fingerprints,
+	source::{extract_meta_pages, process_section_with, Normalizer},
+};
+
+/// How much each signal counts towards a candidate's confidence.
+const TITLE_WEIGHT: f32 = 1.0;
+const PRODUCER_WEIGHT: f32 = 0.5;
+const PAGE_COUNT_WEIGHT: f32 = 1.0;
+const PAGES_WEIGHT: f32 = 2.0;
+/// A page count this close to the meta's still counts for half, for printings with a page
+/// more or less.
+const PAGE_COUNT_SLACK: usize = 4;
+
+/// The confidence below which a candidate isn't worth parsing with.
+pub const MIN_CONFIDENCE: f32 = 0.5;
+
+/// A meta that may be the one for a PDF.
+#[derive(Clone, Debug)]
+pub struct Candidate<'a> {
+	pub meta: &'a SourceMeta,
+	/// From 0 to 1, with 1 when every signal both the meta and the PDF give matches.
+	pub confidence: f32,
+}
+
+/// The [`Identity`] of the PDF at `path`, as it would be given in its meta.
+///
+/// # Errors
+///
+/// If the PDF can't be opened or one of its first pages can't be read.
+pub fn identity(path: impl AsRef<Path>) -> Result<Identity> {
+	let document = backend::default().open(path.as_ref())?;
+
+	identity_of(document.as_ref())
+}
+
+/// The [`Identity`] of `document`.
+///
+/// # Errors
+///
+/// If one of the document's first pages can't be read.
+pub fn identity_of(document: &dyn TextDocument) -> Result<Identity> {
+	let info = document.info();
+
+	Ok(Identity {
+		title: info.title,
+		producer: info.producer,
+		page_count: Some(document.page_count()),
+		pages: front_page_fingerprints(document)?,
+	})
+}
+
+/// The metas the PDF at `path` may be for, likeliest first, going by its title and producer,
+/// its page count and the text of its first few pages.
+pub fn identify_with<'a>(
+	path: impl AsRef<Path>,
+	metas: impl IntoIterator<Item = &'a SourceMeta>,
+	backend: &dyn TextBackend,
+) -> Result<Vec<Candidate<'a>>> {
+	let identity = identity_of(backend.open(path.as_ref())?.as_ref())?;
+
+	let mut candidates: Vec<_> = metas
+		.into_iter()
+		.map(|meta| Candidate {
+			meta,
+			confidence: confidence(meta, &identity),
+		})
+		.filter(|candidate| candidate.confidence > 0.0)
+		.collect();
+	candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+	Ok(candidates)
+}
+
+/// Like [`identify_with`], over the embedded metas and with the default backend.
+#[cfg(feature = "embed_meta")]
+pub fn identify(path: impl AsRef<Path>) -> Result<Vec<Candidate<'static>>> {
+	identify_with(path, crate::META.iter(), backend::default())
+}
+
+fn confidence(meta: &SourceMeta, pdf: &Identity) -> f32 {
+	let expected = &meta.identity;
+	// Only the signals both sides give count, so a meta without an identity can still be
+	// sure of a PDF by its title.
+	let mut score = 0.0;
+	let mut weight = 0.0;
+
+	if let Some(title) = &pdf.title {
+		let name = expected.title.as_ref().unwrap_or(&meta.info.name);
+		score += TITLE_WEIGHT * similarity(name, title);
+		weight += TITLE_WEIGHT;
+	}
+	if let (Some(expected), Some(producer)) = (&expected.producer, &pdf.producer) {
+		if expected == producer {
+			score += PRODUCER_WEIGHT;
+		}
+		weight += PRODUCER_WEIGHT;
+	}
+	if let (Some(expected), Some(count)) = (expected.page_count, pdf.page_count) {
+		weight += PAGE_COUNT_WEIGHT;
+		if expected == count {
+			score += PAGE_COUNT_WEIGHT;
+		} else if expected.abs_diff(count) <= PAGE_COUNT_SLACK {
+			score += PAGE_COUNT_WEIGHT / 2.0;
+		}
+	}
+	if !expected.pages.is_empty() && !pdf.pages.is_empty() {
+		let found = expected
+			.pages
+			.iter()
+			.filter(|page| pdf.pages.contains(page))
+			.count();
+		#[allow(clippy::cast_precision_loss)]
+		let found = found as f32 / expected.pages.len() as f32;
+		score += PAGES_WEIGHT * found;
+		weight += PAGES_WEIGHT;
+	}
+
+	if weight > 0.0 {
+		score / weight
+	} else {
+		0.0
+	}
+}
+
+/// How many of the words of `a` and `b` they share, from 0 to 1.
+fn similarity(a: &str, b: &str) -> f32 {
+	let words = |text: &str| -> HashSet<String> {
+		text.split(|c: char| !c.is_alphanumeric())
+			.filter(|word| !word.is_empty())
+			.map(str::to_lowercase)
+			.collect()
+	};
+	let (a, b) = (words(a), words(b));
+	let union = a.union(&b).count();
+	if union == 0 {
+		return 0.0;
+	}
+
+	#[allow(clippy::cast_precision_loss)]
+	let similarity = a.intersection(&b).count() as f32 / union as f32;
+	similarity
+}
+
+/// Whether every section of `meta` cut out with a [`SectionRange::Regex`] finds it on its
+/// pages of the PDF at `path`, in any of the book's printings, which a guessed meta should
+/// before it's parsed with. A meta without any regexes passes.
+///
+/// # Errors
+///
+/// If the PDF can't be opened or read.
+pub fn anchors_match(
+	path: impl AsRef<Path>,
+	meta: &SourceMeta,
+	backend: &dyn TextBackend,
+) -> Result<bool> {
+	Ok(matching_printing(path, meta, backend)?.is_some())
+}
+
+/// The printing of `meta`, itself or one of its [`cofd_meta::Variant`]s, whose anchors match
+/// the PDF at `path`, as [`anchors_match`] checks them.
+///
+/// The pages are read as the parse reads them, with the printing's column layouts, OCR and
+/// running text removal, and each section's text is put together by
+/// [`process_section_with`], so an anchor matches here exactly when it matches in the parse.
+///
+/// # Errors
+///
+/// If the PDF can't be opened or read.
+pub fn matching_printing<'a>(
+	path: impl AsRef<Path>,
+	meta: &'a SourceMeta,
+	backend: &dyn TextBackend,
+) -> Result<Option<Cow<'a, SourceMeta>>> {
+	let document = backend.open(path.as_ref())?;
+	let normalizer = Normalizer::new(&meta.normalization);
+
+	let printings = std::iter::once(Cow::Borrowed(meta)).chain(
+		meta.variants
+			.iter()
+			.map(|variant| Cow::Owned(meta.with_variant(variant))),
+	);
+	for printing in printings {
+		if anchors_match_pages(document.as_ref(), &printing, &normalizer)? {
+			return Ok(Some(printing));
+		}
+	}
+
+	Ok(None)
+}
+
+fn anchors_match_pages(
+	document: &dyn TextDocument,
+	meta: &SourceMeta,
+	normalizer: &Normalizer,
+) -> Result<bool> {
+	let anchored: Vec<_> = meta
+		.sections
+		.iter()
+		.filter_map(|section| match &section.range {
+			Some(SectionRange::Regex(regex)) => Some((section, regex)),
+			_ => None,
+		})
+		.collect();
+	if anchored.is_empty() {
+		return Ok(true);
+	}
+	if anchored
+		.iter()
+		.any(|(section, _)| *section.pages.end() >= document.page_count())
+	{
+		return Ok(false);
+	}
+
+	let pages = extract_meta_pages(document, meta)?;
+	for (section, regex) in anchored {
+		// The range is found in the text before emphasis is marked, and without cutting it
+		// down or applying the ops.
+		let whole = SectionMeta {
+			emphasis: false,
+			..section.clone()
+		};
+		let text = process_section_with(&pages, &whole, true, normalizer)?.original;
+		if !regex.is_match(&text) {
+			return Ok(false);
+		}
+	}
+
+	Ok(true)
+}
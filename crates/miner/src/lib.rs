@@ -5,48 +5,136 @@
 	clippy::similar_names
 )]
 
+#[cfg(feature = "embed_meta")]
+use std::borrow::Cow;
 use std::path::Path;
 
+use backend::TextBackend;
 use cofd_meta::SourceMeta;
-use cofd_schema::{book::Book, DOT_CHAR};
+use cofd_schema::DOT_CHAR;
 use error::CofDMinerError;
-use hash::hash;
 use once_cell::sync::Lazy;
-use parse::PdfExtract;
+use parse::{ParsedBook, PdfExtract};
 use regex::Regex;
 
-mod backend;
-
+pub mod backend;
 pub mod error;
 pub mod hash;
+pub mod identify;
+pub mod meta;
 pub mod parse;
+pub mod schema;
 pub mod source;
+pub mod validate;
 
-pub use source::{extract_pages, extract_text, process_section};
-
-#[cfg(feature = "embed_meta")]
-const META_BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/meta.bin"));
+pub use meta::MetaRegistry;
+pub use source::{
+	extract_meta_pages, extract_pages, extract_text, extract_text_with_backend, process_section,
+	process_section_with,
+};
 
 #[cfg(feature = "embed_meta")]
-static META: Lazy<Vec<SourceMeta>> =
-	Lazy::new(|| rmp_serde::decode::from_slice(META_BYTES).unwrap());
+static META: Lazy<MetaRegistry> = Lazy::new(MetaRegistry::embedded);
 
 static DOT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(&format!("^{DOT_CHAR}+ ")).unwrap());
 
-pub fn parse_book_with_meta(path: impl AsRef<Path>, source: &SourceMeta) -> anyhow::Result<Book> {
-	extract_text(path, source).and_then(PdfExtract::parse)
+pub fn parse_book_with_meta(
+	path: impl AsRef<Path>,
+	source: &SourceMeta,
+) -> anyhow::Result<ParsedBook> {
+	extract_text(path, source).map(PdfExtract::parse)
+}
+
+pub fn parse_book_with_meta_and_backend(
+	path: impl AsRef<Path>,
+	source: &SourceMeta,
+	backend: &dyn TextBackend,
+) -> anyhow::Result<ParsedBook> {
+	extract_text_with_backend(path, source, backend).map(PdfExtract::parse)
+}
+
+/// Like [`MetaRegistry::get_by_hash`], in the embedded metas.
+#[must_use]
+#[cfg(feature = "embed_meta")]
+pub fn get_meta_by_hash(hash: u64) -> Option<Cow<'static, SourceMeta>> {
+	META.get_by_hash(hash)
+}
+
+#[must_use]
+#[cfg(feature = "embed_meta")]
+pub fn get_meta_by_fingerprint(fingerprint: u64) -> Option<Cow<'static, SourceMeta>> {
+	META.get_by_fingerprint(fingerprint)
 }
 
+/// The embedded meta for the book with hash `hash`, not counting its other printings.
 #[must_use]
 #[cfg(feature = "embed_meta")]
+#[deprecated(
+	note = "use `get_meta_by_hash`, or `embedded_metas().get`, which also find other printings"
+)]
 pub fn get_meta(hash: u64) -> Option<&'static SourceMeta> {
-	META.iter().find(|source| source.info.hash.eq(&hash))
+	META.iter().find(|source| source.info.hash == hash)
 }
 
+/// The metas embedded at build time, to look PDFs up in with [`MetaRegistry::get`].
+#[must_use]
 #[cfg(feature = "embed_meta")]
-pub fn parse_book(path: impl AsRef<Path>) -> anyhow::Result<Book> {
-	let hash = hash(&path)?;
-	let meta = get_meta(hash).ok_or(CofDMinerError::NoSuchMeta)?;
+pub fn embedded_metas() -> &'static MetaRegistry {
+	&META
+}
+
+/// Like [`parse_book`], but matching the PDF against the metas in `registry`.
+pub fn parse_book_with_registry(
+	path: impl AsRef<Path>,
+	registry: &MetaRegistry,
+) -> anyhow::Result<ParsedBook> {
+	let meta = registry.get(&path)?.ok_or(CofDMinerError::NoSuchMeta)?;
+
+	parse_book_with_meta(path, &meta)
+}
+
+#[cfg(feature = "embed_meta")]
+pub fn parse_book(path: impl AsRef<Path>) -> anyhow::Result<ParsedBook> {
+	let meta = META.get(&path)?.ok_or(CofDMinerError::NoSuchMeta)?;
+
+	parse_book_with_meta(path, &meta)
+}
+
+/// Like [`parse_book`], but extracting the text with `backend`.
+#[cfg(feature = "embed_meta")]
+pub fn parse_book_with_backend(
+	path: impl AsRef<Path>,
+	backend: &dyn TextBackend,
+) -> anyhow::Result<ParsedBook> {
+	let meta = META.get(&path)?.ok_or(CofDMinerError::NoSuchMeta)?;
+
+	parse_book_with_meta_and_backend(path, &meta, backend)
+}
+
+/// Like [`parse_book`], but when no meta matches the PDF, parses it with the
+/// likeliest meta [`identify::identify`] finds whose anchors match it, if one is likely
+/// enough.
+#[cfg(feature = "embed_meta")]
+pub fn parse_book_identified(path: impl AsRef<Path>) -> anyhow::Result<ParsedBook> {
+	if let Some(meta) = META.get(&path)? {
+		return parse_book_with_meta(path, &meta);
+	}
+
+	for candidate in identify::identify(&path)?
+		.into_iter()
+		.filter(|candidate| candidate.confidence >= identify::MIN_CONFIDENCE)
+	{
+		if let Some(meta) = identify::matching_printing(&path, candidate.meta, backend::default())?
+		{
+			log::warn!(
+				"No meta matches {}, so it's parsed as {} ({:.0}% confidence)",
+				path.as_ref().display(),
+				candidate.meta.info.name,
+				candidate.confidence * 100.0
+			);
+			return parse_book_with_meta(path, &meta);
+		}
+	}
 
-	parse_book_with_meta(path, meta)
+	Err(CofDMinerError::NoSuchMeta.into())
 }
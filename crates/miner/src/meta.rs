@@ -0,0 +1,199 @@
+//! The metas PDFs are matched against, loaded at runtime as well as embedded.
+
+use std::{
+	borrow::Cow,
+	fs::{self, File},
+	path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use cofd_meta::SourceMeta;
+
+use crate::{
+	hash::{fingerprint, hash},
+	validate::shared_keys,
+};
+
+#[cfg(feature = "embed_meta")]
+const META_BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/meta.bin"));
+
+/// A set of [`SourceMeta`]s, each keyed by its book's hash.
+///
+/// A meta that shares a hash or fingerprint with one already in the registry, in any printing,
+/// replaces it. The embedded metas are added first, then each directory or file in the order
+/// it's loaded, with the files in a directory in order of their names.
+#[derive(Clone, Debug, Default)]
+pub struct MetaRegistry {
+	/// Each meta, with the file it was loaded from.
+	metas: Vec<(SourceMeta, Option<PathBuf>)>,
+}
+
+impl MetaRegistry {
+	/// A registry with no metas at all.
+	#[must_use]
+	pub fn empty() -> Self {
+		Self::default()
+	}
+
+	/// A registry with the metas embedded at build time from the repository's `meta/`.
+	///
+	/// # Panics
+	///
+	/// If the embedded metas don't decode, which `build.rs` should have caught.
+	#[must_use]
+	#[cfg(feature = "embed_meta")]
+	pub fn embedded() -> Self {
+		let metas: Vec<SourceMeta> = rmp_serde::decode::from_slice(META_BYTES).unwrap();
+
+		let mut registry = Self::empty();
+		for meta in metas {
+			registry.insert(meta, None);
+		}
+		registry
+	}
+
+	pub fn add(&mut self, meta: SourceMeta) -> &mut Self {
+		self.insert(meta, None);
+		self
+	}
+
+	#[must_use]
+	pub fn with(mut self, meta: SourceMeta) -> Self {
+		self.add(meta);
+		self
+	}
+
+	/// Adds the meta in the JSON file at `path`.
+	pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<&mut Self> {
+		let path = path.as_ref();
+		self.insert(load(path)?, Some(path.to_owned()));
+		Ok(self)
+	}
+
+	pub fn with_file(mut self, path: impl AsRef<Path>) -> Result<Self> {
+		self.load_file(path)?;
+		Ok(self)
+	}
+
+	/// Adds the meta in each JSON file directly in `dir`.
+	pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<&mut Self> {
+		for path in files(dir)? {
+			self.load_file(path)?;
+		}
+		Ok(self)
+	}
+
+	pub fn with_dir(mut self, dir: impl AsRef<Path>) -> Result<Self> {
+		self.load_dir(dir)?;
+		Ok(self)
+	}
+
+	fn insert(&mut self, meta: SourceMeta, path: Option<PathBuf>) {
+		self.metas.retain(|(other, _)| {
+			let shared = shared_keys(&meta, other);
+			if !shared.is_empty() && other.info.name != meta.info.name {
+				log::warn!(
+					"{} replaces {}, as they share {}",
+					meta.info.name,
+					other.info.name,
+					shared.join(", ")
+				);
+			}
+			shared.is_empty()
+		});
+		self.metas.push((meta, path));
+	}
+
+	/// The metas, in the order they were added.
+	pub fn iter(&self) -> impl Iterator<Item = &SourceMeta> {
+		self.metas.iter().map(|(meta, _)| meta)
+	}
+
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.metas.len()
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.metas.is_empty()
+	}
+
+	/// The file the meta for the printing with file hash `hash` was loaded from, unless it was
+	/// embedded or added directly.
+	#[must_use]
+	pub fn path(&self, hash: u64) -> Option<&Path> {
+		self.metas
+			.iter()
+			.rev()
+			.find(|(meta, _)| {
+				meta.info.hash == hash || meta.variants.iter().any(|variant| variant.hash == hash)
+			})
+			.and_then(|(_, path)| path.as_deref())
+	}
+
+	/// The meta for the printing with file hash `hash`, which is owned when the hash is one of
+	/// a book's other [`cofd_meta::Variant`]s. The meta added last wins.
+	#[must_use]
+	pub fn get_by_hash(&self, hash: u64) -> Option<Cow<'_, SourceMeta>> {
+		self.metas
+			.iter()
+			.rev()
+			.find_map(|(meta, _)| meta.for_hash(hash))
+	}
+
+	#[must_use]
+	pub fn get_by_fingerprint(&self, fingerprint: u64) -> Option<Cow<'_, SourceMeta>> {
+		self.metas
+			.iter()
+			.rev()
+			.find_map(|(meta, _)| meta.for_fingerprint(fingerprint))
+	}
+
+	/// The meta for the PDF at `path`, matched on its exact hash, or failing that on its
+	/// [`crate::hash::fingerprint`], which is slower to take but survives storefront
+	/// watermarks. The fingerprint is only taken if some meta records one.
+	pub fn get(&self, path: impl AsRef<Path>) -> Result<Option<Cow<'_, SourceMeta>>> {
+		if let Some(meta) = self.get_by_hash(hash(&path)?) {
+			return Ok(Some(meta));
+		}
+		if !self.has_fingerprints() {
+			return Ok(None);
+		}
+
+		Ok(self.get_by_fingerprint(fingerprint(&path)?))
+	}
+
+	/// Whether any meta, or any of its variants, records a fingerprint.
+	fn has_fingerprints(&self) -> bool {
+		self.iter().any(|meta| {
+			meta.fingerprint.is_some()
+				|| meta
+					.variants
+					.iter()
+					.any(|variant| variant.fingerprint.is_some())
+		})
+	}
+}
+
+/// Reads the meta in the JSON file at `path`.
+pub fn load(path: impl AsRef<Path>) -> Result<SourceMeta> {
+	let path = path.as_ref();
+
+	serde_json::from_reader(File::open(path)?).map_err(|err| anyhow!("{}: {}", path.display(), err))
+}
+
+/// The JSON files directly in `dir`, in the order [`MetaRegistry::load_dir`] loads them.
+pub fn files(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+	let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+		.map(|entry| Ok(entry?.path()))
+		.filter(|path| {
+			path.as_ref().map_or(true, |path| {
+				path.extension().is_some_and(|ext| ext == "json")
+			})
+		})
+		.collect::<Result<_>>()?;
+	paths.sort();
+
+	Ok(paths)
+}
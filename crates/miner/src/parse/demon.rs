@@ -0,0 +1,205 @@
+use std::{ops::Range, str::FromStr};
+
+use cofd_meta::PageKind;
+use cofd_schema::{item::Item, prelude::BookInfo};
+
+use super::{
+	get_body_after,
+	item::{ItemProp, PROP_REGEX},
+	normalize,
+	report::ParseReport,
+	text_reference, Hyphenation,
+};
+use crate::{
+	parse::item::RawItem,
+	schema::{Cipher, CipherItem, Embed, EmbedItem, Exploit, ExploitItem},
+	source::Section,
+};
+
+/// Words left lowercase in a power's title-cased name.
+const MINOR_WORDS: &[&str] = &[
+	"a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "of", "on", "or",
+	"over", "the", "to", "upon", "with",
+];
+const MAX_NAME_LEN: usize = 50;
+
+/// The properties that open an Embed's or Exploit's rules block.
+const ACTION_PROPS: &[ItemProp] = &[
+	ItemProp::Prerequisites,
+	ItemProp::Cost,
+	ItemProp::DicePool,
+	ItemProp::Action,
+];
+/// The properties that make up a Cipher.
+const CIPHER_PROPS: &[ItemProp] = &[ItemProp::Interlocks, ItemProp::FinalTruth];
+
+pub fn parse_embeds(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<EmbedItem> {
+	let PageKind::Embed(incarnation) = &section.kind else {
+		report.error(section, "", "Section is not an embed section");
+		return Vec::new();
+	};
+
+	parse_powers(
+		info,
+		hyphenation,
+		section,
+		report,
+		ACTION_PROPS,
+		|raw_item| Embed {
+			incarnation: incarnation.clone(),
+			action: raw_item.action(),
+		},
+	)
+}
+
+pub fn parse_exploits(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<ExploitItem> {
+	parse_powers(
+		info,
+		hyphenation,
+		section,
+		report,
+		ACTION_PROPS,
+		|raw_item| Exploit {
+			prerequisites: raw_item.take(Some(ItemProp::Prerequisites)),
+			action: raw_item.action(),
+		},
+	)
+}
+
+pub fn parse_ciphers(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<CipherItem> {
+	parse_powers(
+		info,
+		hyphenation,
+		section,
+		report,
+		CIPHER_PROPS,
+		|raw_item| {
+			// Properties are read bottom-up, so repeated ones come out reversed.
+			let mut interlocks = raw_item.take(Some(ItemProp::Interlocks));
+			interlocks.reverse();
+
+			Cipher {
+				interlocks,
+				final_truth: raw_item.take(Some(ItemProp::FinalTruth)),
+			}
+		},
+	)
+}
+
+fn parse_powers<T>(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+	props: &[ItemProp],
+	inner: impl Fn(&mut RawItem) -> T,
+) -> Vec<Item<T>> {
+	let mut out = Vec::new();
+	let mut str_pos = section.extract.len();
+
+	for header in headers(&section.extract, props).into_iter().rev() {
+		let header_text = &section.extract[header.clone()];
+		let name = normalize(header_text.trim());
+		let reference = text_reference(header_text, section, info, report);
+		let body = get_body_after(&mut str_pos, &section.extract, header);
+
+		let mut raw_item = {
+			let v: Vec<&str> = body.iter().map(String::as_str).collect();
+			match RawItem::parse(v, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, header_text, err.to_string());
+					continue;
+				}
+			}
+		};
+
+		let inner = inner(&mut raw_item);
+		out.push(Item {
+			name,
+			reference,
+			description: raw_item.take(None),
+			effects: raw_item.take(Some(ItemProp::Effects)),
+			inner,
+		});
+	}
+
+	out
+}
+
+/// Finds the name lines of the powers in `text`.
+///
+/// Embeds, Exploits and Ciphers have no dot rating or tags, and their description can come
+/// before or after their rules, so a name is told apart by its layout: a short, title-cased
+/// line that doesn't start a paragraph, follows the end of a sentence or of a rules block,
+/// and is followed by one of `props` before the next name.
+fn headers(text: &str, props: &[ItemProp]) -> Vec<Range<usize>> {
+	let mut lines = Vec::new();
+	let mut start = 0;
+	for line in text.split('\n') {
+		lines.push(start..start + line.len());
+		start += line.len() + 1;
+	}
+	let line = |i: usize| &text[lines[i].clone()];
+
+	let candidates = (0..lines.len()).filter(|&i| {
+		is_name(line(i)) && (i == 0 || ends_block(line(i - 1)) || is_name(line(i - 1)))
+	});
+
+	let mut headers = Vec::new();
+	let mut end = lines.len();
+	for i in candidates.collect::<Vec<_>>().into_iter().rev() {
+		if (i + 1..end).any(|j| opens(line(j), props)) {
+			headers.push(lines[i].clone());
+			end = i;
+		}
+	}
+
+	headers.reverse();
+	headers
+}
+
+fn is_name(line: &str) -> bool {
+	let line = line.trim_end();
+	if line.is_empty()
+		|| line.len() > MAX_NAME_LEN
+		|| line.starts_with('\t')
+		|| line.contains(':')
+		|| line.ends_with(['.', ',', ';', '!', '?', '-'])
+	{
+		return false;
+	}
+
+	let capitalized = |word: &str| word.chars().next().is_some_and(|c| !c.is_lowercase());
+	let mut words = line.split_whitespace();
+	words.next().is_some_and(capitalized)
+		&& words.all(|word| capitalized(word) || MINOR_WORDS.contains(&word))
+}
+
+/// Whether a name may follow `line`: the end of a sentence or a rules line.
+fn ends_block(line: &str) -> bool {
+	let line = line.trim_end();
+	line.ends_with(['.', '!', '?', '"', '”', ')']) || PROP_REGEX.is_match(line.trim_start())
+}
+
+fn opens(line: &str, props: &[ItemProp]) -> bool {
+	PROP_REGEX
+		.captures(line.trim_start())
+		.and_then(|captures| ItemProp::from_str(&captures[1]).ok())
+		.is_some_and(|prop| props.contains(&prop))
+}
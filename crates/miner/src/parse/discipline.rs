@@ -0,0 +1,190 @@
+use cofd_meta::PageKind;
+use cofd_schema::{dot_range::dots_to_num, item::Item, prelude::BookInfo};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{
+	get_body, get_book_reference, item::ItemProp, normalize, report::ParseReport, Hyphenation,
+};
+use crate::{
+	parse::item::RawItem,
+	schema::{Devotion, DevotionItem, DisciplinePower, DisciplinePowerItem, DisciplineRating},
+	source::Section,
+};
+
+static POWER_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
+	Regex::new(
+		r"(?xm)
+		^\t*
+		(?:
+			(?<ldots> •{1,5} ) \s+ (?<lname> [^\s•(] [^\n•(]+? )     # • Name
+			|
+			(?<rname> [^\s•(] [^\n•(]+? )                            # Name (Discipline •)
+			\s?
+			\(
+				(?: (?<discipline> [^\n•()]+? ) \s )?
+				(?<rdots> •{1,5} )
+			\)
+		)
+		\s?
+		$
+	",
+	)
+	.unwrap()
+});
+
+static DEVOTION_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
+	Regex::new(
+		r"(?xm)
+		^\t*
+		(?<name> [^\s•:] [^\n•:]+? ) \s?                         # Name
+		\n
+		\t* Prerequisites?: \s? (?<prereqs> [^\n]* • [^\n]* )   # Prerequisites
+		$
+	",
+	)
+	.unwrap()
+});
+
+static RATING_REGEX: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"(?<discipline>[^\s,•][^,•]*?)\s*(?<dots>•{1,5})").unwrap());
+
+pub fn parse_disciplines(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<DisciplinePowerItem> {
+	let mut out = Vec::new();
+	let mut str_pos = section.extract.len();
+
+	let PageKind::Discipline(discipline) = &section.kind else {
+		report.error(section, "", "Section is not a discipline section");
+		return out;
+	};
+
+	for captures in POWER_HEADER_REGEX
+		.captures_iter(&section.extract)
+		.collect::<Vec<_>>()
+		.into_iter()
+		.rev()
+	{
+		let header = captures.get(0).unwrap().as_str();
+		let name = captures
+			.name("lname")
+			.or_else(|| captures.name("rname"))
+			.map(|name| normalize(name.as_str().trim()))
+			.unwrap_or_default();
+		let level = captures
+			.name("ldots")
+			.or_else(|| captures.name("rdots"))
+			.and_then(|dots| dots_to_num(dots.as_str()))
+			.unwrap_or(0);
+		let discipline = captures
+			.name("discipline")
+			.map_or_else(|| discipline.clone(), |d| d.as_str().trim().to_owned());
+
+		let reference = get_book_reference(&captures, section, info, report);
+		let body = get_body(&mut str_pos, &section.extract, &captures);
+
+		let mut raw_item = {
+			let v: Vec<&str> = body.iter().map(String::as_str).collect();
+			match RawItem::parse(v, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, header, err.to_string());
+					continue;
+				}
+			}
+		};
+
+		let action = raw_item.action();
+		if action.is_none() {
+			report.warning(section, header, "Power has no Cost/Dice Pool/Action block");
+		}
+
+		out.push(Item {
+			name,
+			reference,
+			description: raw_item.take(None),
+			effects: raw_item.take(Some(ItemProp::Effects)),
+			inner: DisciplinePower {
+				discipline,
+				level,
+				action,
+			},
+		});
+	}
+
+	out.reverse();
+	out
+}
+
+pub fn parse_devotions(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<DevotionItem> {
+	let mut out = Vec::new();
+	let mut str_pos = section.extract.len();
+
+	for captures in DEVOTION_HEADER_REGEX
+		.captures_iter(&section.extract)
+		.collect::<Vec<_>>()
+		.into_iter()
+		.rev()
+	{
+		let header = captures.get(0).unwrap().as_str();
+		let name = normalize(captures.name("name").unwrap().as_str().trim());
+		let reference = get_book_reference(&captures, section, info, report);
+
+		let prerequisites: Vec<DisciplineRating> = RATING_REGEX
+			.captures_iter(captures.name("prereqs").unwrap().as_str())
+			.map(|rating| DisciplineRating {
+				discipline: rating["discipline"]
+					.trim()
+					.trim_start_matches("or ")
+					.trim_start_matches("and ")
+					.to_owned(),
+				level: dots_to_num(&rating["dots"]).unwrap_or(0),
+			})
+			.collect();
+
+		let body = get_body(&mut str_pos, &section.extract, &captures);
+		let mut raw_item = {
+			let v: Vec<&str> = body.iter().map(String::as_str).collect();
+			match RawItem::parse(v, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, header, err.to_string());
+					continue;
+				}
+			}
+		};
+
+		let experience_cost = raw_item.take(Some(ItemProp::ExperienceCost)).concat();
+		let experience_cost = experience_cost
+			.split(|c: char| !c.is_ascii_digit())
+			.find(|s| !s.is_empty())
+			.and_then(|s| s.parse().ok());
+		if experience_cost.is_none() {
+			report.warning(section, header, "Devotion has no experience cost");
+		}
+
+		out.push(Item {
+			name,
+			reference,
+			description: raw_item.take(None),
+			effects: raw_item.take(Some(ItemProp::Effects)),
+			inner: Devotion {
+				prerequisites,
+				experience_cost,
+				action: raw_item.action(),
+			},
+		});
+	}
+
+	out.reverse();
+	out
+}
@@ -1,21 +1,21 @@
 use std::str::FromStr;
 
-use anyhow::Result;
 use cofd_meta::PageKind;
 use cofd_schema::{
 	book::OtherGift,
+	dot_range::dots_to_num,
 	item::{
-		gift::{Facet, Gift, Other},
+		gift::{Facet, Gift, GiftKind, Moon, Other},
 		Item,
 	},
 	prelude::BookInfo,
-	template::werewolf::Renown,
+	template::werewolf::{Auspice, Renown},
 };
 use convert_case::{Case, Casing};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use super::{get_book_reference, item::ItemProp};
+use super::{get_book_reference, item::ItemProp, report::ParseReport, text_reference, Hyphenation};
 use crate::{parse::item::RawItem, source::Section};
 
 static GIFT_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
@@ -36,16 +36,48 @@ static GIFT_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
 	.unwrap()
 });
 
-pub fn parse_gifts(info: &BookInfo, section: &Section) -> Result<Vec<OtherGift>> {
+static MOON_GIFT_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
+	Regex::new(
+		r"(?xi)
+			^\t*
+			(?:
+				(?P<phase>Crescent|Full|Gibbous|Half|New) \s+ Moon'?s? \s+ Gift  # Moon phase
+				|
+				(?P<name>[^\s(][^\n(]+?)                                     # Name
+				\s?
+				\(
+					(?P<auspice>Cahalith|Elodoth|Irraka|Ithaeur|Rahu)         # Auspice
+				\)
+			)
+			\s?
+			$
+		",
+	)
+	.unwrap()
+});
+
+static MOON_FACET_HEADER_REGEX: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"^\t*(?P<level>•{1,5})\s+(?P<name>[^\n•]+?)\s?$").unwrap());
+
+pub fn parse_gifts(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<OtherGift> {
 	let mut out = Vec::new();
 	let mut facets = Vec::new();
 	let mut body: Vec<&str> = Vec::new();
 
 	let PageKind::Gift(kind) = section.kind else {
-		unreachable!()
+		report.error(section, "", "Section is not a gift section");
+		return out;
 	};
 
+	let mut end = section.extract.len();
 	for line in section.extract.split('\n').rev() {
+		let start = end - line.len();
+		end = start.saturating_sub(1);
 		let lower = line.to_ascii_lowercase();
 
 		let last = body.last().map(|s| s.trim()).unwrap_or_default();
@@ -59,6 +91,14 @@ pub fn parse_gifts(info: &BookInfo, section: &Section) -> Result<Vec<OtherGift>>
 			});
 			facets = Vec::new();
 		} else if let Some(captures) = GIFT_HEADER_REGEX.captures(&haystack.join("\n")) {
+			// The header as it is in the extract, running on to the next line when the renown
+			// is there, so its reference is found where it is.
+			let header = if captures.get(0).unwrap().end() > line.len() {
+				let next = body.last().map_or(0, |next| next.len() + 1);
+				&section.extract[start..start + line.len() + next]
+			} else {
+				line
+			};
 			if last.starts_with('(') && last.ends_with(')') {
 				body.pop();
 			}
@@ -69,11 +109,30 @@ pub fn parse_gifts(info: &BookInfo, section: &Section) -> Result<Vec<OtherGift>>
 				.as_str()
 				.trim()
 				.to_case(Case::Title);
-			let renown = Renown::from_str(captures.name("renown").unwrap().as_str().trim())?;
-			let reference = get_book_reference(&captures, section, info);
+			let renown = captures.name("renown").unwrap().as_str().trim();
+			let renown = match Renown::from_str(renown) {
+				Ok(renown) => renown,
+				Err(err) => {
+					report.error(
+						section,
+						header,
+						format!("Invalid renown \"{renown}\": {err}"),
+					);
+					body = Vec::new();
+					continue;
+				}
+			};
+			let reference = text_reference(header, section, info, report);
 
 			body.reverse();
-			let mut raw_item = RawItem::try_from(body)?;
+			let mut raw_item = match RawItem::parse(body, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, header, err.to_string());
+					body = Vec::new();
+					continue;
+				}
+			};
 
 			facets.push(Item {
 				name: name.clone(),
@@ -92,5 +151,117 @@ pub fn parse_gifts(info: &BookInfo, section: &Section) -> Result<Vec<OtherGift>>
 		}
 	}
 
-	Ok(out)
+	out
+}
+
+pub fn parse_moon_gifts(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<Gift<Moon>> {
+	let mut out = Vec::new();
+	let mut facets: Vec<(Item<Facet<()>>, u8)> = Vec::new();
+	let mut body: Vec<&str> = Vec::new();
+
+	for line in section.extract.split('\n').rev() {
+		if let Some(captures) = MOON_GIFT_HEADER_REGEX.captures(line) {
+			let auspice = if let Some(auspice) = captures.name("auspice") {
+				Auspice::from_str(auspice.as_str().trim()).ok()
+			} else {
+				captures
+					.name("phase")
+					.and_then(|phase| phase_to_auspice(phase.as_str()))
+			};
+			let Some(auspice) = auspice else {
+				report.error(section, line, "Unknown auspice");
+				facets = Vec::new();
+				body = Vec::new();
+				continue;
+			};
+
+			let name = captures.name("name").map_or_else(
+				|| line.trim().to_case(Case::Title),
+				|name| name.as_str().trim().to_case(Case::Title),
+			);
+
+			facets.reverse();
+			out.push(Gift {
+				name,
+				facets: facets
+					.into_iter()
+					.map(|(facet, level)| Item {
+						name: facet.name,
+						reference: facet.reference,
+						description: facet.description,
+						effects: facet.effects,
+						inner: Facet {
+							action: facet.inner.action,
+							inner: Moon { level, auspice },
+						},
+					})
+					.collect(),
+				kind: GiftKind::Moon,
+			});
+			facets = Vec::new();
+			body = Vec::new();
+		} else if let Some(captures) = MOON_FACET_HEADER_REGEX.captures(line) {
+			let name = captures.name("name").unwrap().as_str().trim().to_owned();
+			let level = dots_to_num(captures.name("level").unwrap().as_str()).unwrap_or(0);
+			let reference = get_book_reference(&captures, section, info, report);
+
+			body.reverse();
+			let mut raw_item = match RawItem::parse(body, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, line, err.to_string());
+					body = Vec::new();
+					continue;
+				}
+			};
+
+			facets.push((
+				Item {
+					name,
+					reference,
+					description: raw_item.take(None),
+					effects: raw_item.take(Some(ItemProp::Effects)),
+					inner: Facet {
+						action: raw_item.action(),
+						inner: (),
+					},
+				},
+				level,
+			));
+			body = Vec::new();
+		} else {
+			body.push(line);
+		}
+	}
+
+	if !facets.is_empty() {
+		report.warning(
+			section,
+			"",
+			format!(
+				"{} Moon gift facets found before any gift header",
+				facets.len()
+			),
+		);
+	}
+
+	out.reverse();
+	out
+}
+
+/// Each Moon gift belongs to the auspice born under that phase of Luna.
+fn phase_to_auspice(phase: &str) -> Option<Auspice> {
+	match phase.to_ascii_lowercase().as_str() {
+		"crescent" => Some(Auspice::Ithaeur),
+		"full" => Some(Auspice::Rahu),
+		"gibbous" => Some(Auspice::Cahalith),
+		"half" => Some(Auspice::Elodoth),
+		"new" => Some(Auspice::Irraka),
+		_ => None,
+	}
 }
@@ -10,11 +10,11 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use strum::EnumString;
 
-use crate::parse::paragraph::to_paragraphs;
+use crate::parse::paragraph::{to_paragraphs, Hyphenation};
 
 pub static PROP_REGEX: Lazy<Regex> = Lazy::new(|| {
 	Regex::new(
-		r"^(Prerequisite|Style Tag|Cost|Dice Pool|Action|Duration|Effect|Drawback|Note|Exceptional Success|Success|Failure|Dramatic Failure|Suggested Modifiers)s?:\s?(.*)$"
+		r"^(Prerequisite|Style Tag|Cost|Dice Pool|Action|Duration|Effect|Drawback|Note|Exceptional Success|Success|Failure|Dramatic Failure|Suggested Modifiers|Practice|Primary Factor|Withstand|Suggested Rote Skill|Experience Cost|Experience|Interlock|Final Truth)s?:\s?(.*)$"
 	)
 		.unwrap()
 });
@@ -48,6 +48,24 @@ pub enum ItemProp {
 	Drawbacks,
 	#[strum(serialize = "Notes", serialize = "Note")]
 	Notes,
+
+	Practice,
+	#[strum(to_string = "Primary Factor")]
+	PrimaryFactor,
+	Withstand,
+	#[strum(
+		to_string = "Suggested Rote Skills",
+		serialize = "Suggested Rote Skill"
+	)]
+	SuggestedRoteSkills,
+
+	#[strum(to_string = "Experience Cost", serialize = "Experience")]
+	ExperienceCost,
+
+	#[strum(serialize = "Interlocks", serialize = "Interlock")]
+	Interlocks,
+	#[strum(to_string = "Final Truth")]
+	FinalTruth,
 }
 
 #[derive(Debug, Default)]
@@ -115,10 +133,9 @@ impl RawItem {
 	}
 }
 
-impl TryFrom<Vec<&str>> for RawItem {
-	type Error = anyhow::Error;
-
-	fn try_from(body: Vec<&str>) -> Result<Self, Self::Error> {
+impl RawItem {
+	/// Reads an item's properties out of its body, joining lines into paragraphs.
+	pub fn parse(body: Vec<&str>, hyphenation: &Hyphenation) -> anyhow::Result<Self> {
 		let mut lines: Vec<String> = Vec::new();
 		let mut first_prop = true;
 
@@ -142,13 +159,13 @@ impl TryFrom<Vec<&str>> for RawItem {
 					match prop_key {
 						ItemProp::Prerequisites => raw_item.push(
 							Some(ItemProp::Prerequisites),
-							to_paragraphs(&lines)[0]
+							to_paragraphs(&lines, hyphenation)[0]
 								.split(", ")
 								.map(ToString::to_string)
 								.collect(),
 						),
 						ItemProp::Effects => raw_item.push(Some(ItemProp::Effects), lines), // Effects are rolled into paragraphs later
-						_ => raw_item.push(Some(prop_key), to_paragraphs(&lines)),
+						_ => raw_item.push(Some(prop_key), to_paragraphs(&lines, hyphenation)),
 					}
 					lines = Vec::new();
 				}
@@ -174,11 +191,11 @@ impl TryFrom<Vec<&str>> for RawItem {
 
 		if let Some(description) = raw_item.get_mut(None) {
 			description.reverse();
-			*description = to_paragraphs(description);
+			*description = to_paragraphs(description, hyphenation);
 		}
 		if let Some(effects) = raw_item.get_mut(Some(ItemProp::Effects)) {
 			effects.reverse();
-			*effects = to_paragraphs(effects);
+			*effects = to_paragraphs(effects, hyphenation);
 		}
 
 		Ok(raw_item)
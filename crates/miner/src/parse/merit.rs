@@ -12,9 +12,14 @@ use convert_case::{Case, Casing};
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
 
-use super::{get_body, get_book_reference, item::ItemProp, normalize, parse_name};
+use super::{
+	get_body, get_book_reference, item::ItemProp, normalize, parse_name, report::ParseReport,
+};
 use crate::{
-	parse::{item::RawItem, paragraph::to_paragraphs},
+	parse::{
+		item::RawItem,
+		paragraph::{to_paragraphs, Hyphenation},
+	},
 	source::Section,
 };
 
@@ -45,14 +50,20 @@ static MERIT_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
 	.unwrap()
 });
 
-pub fn parse_merits(info: &BookInfo, section: &Section) -> Result<Vec<MeritItem>> {
+pub fn parse_merits(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<MeritItem> {
 	let mut out = Vec::new();
 	let mut children: Vec<MeritSubItem> = Vec::new();
 
 	let mut str_pos = section.extract.len();
 
 	let PageKind::Merit(additional_prerequisites) = &section.kind else {
-		unreachable!()
+		report.error(section, "", "Section is not a merit section");
+		return out;
 	};
 	let additional_prerequisites = additional_prerequisites
 		.as_ref()
@@ -64,12 +75,16 @@ pub fn parse_merits(info: &BookInfo, section: &Section) -> Result<Vec<MeritItem>
 		.into_iter()
 		.rev()
 	{
+		let header = captures.get(0).unwrap().as_str();
 		let sub = captures.name("sub");
 		let cost = captures.name("cost").unwrap();
 
 		let name = parse_name(&captures);
-		let reference = get_book_reference(&captures, section, info);
-		let tags = process_tags(&captures)?;
+		let reference = get_book_reference(&captures, section, info, report);
+		let tags = process_tags(&captures).unwrap_or_else(|err| {
+			report.warning(section, header, err.to_string());
+			Vec::new()
+		});
 
 		let mut body = get_body(&mut str_pos, &section.extract, &captures);
 		if let Some(sub) = &sub {
@@ -80,7 +95,17 @@ pub fn parse_merits(info: &BookInfo, section: &Section) -> Result<Vec<MeritItem>
 
 		let mut raw_item = {
 			let v: Vec<&str> = body.iter().map(String::as_str).collect();
-			RawItem::try_from(v)?
+			match RawItem::parse(v, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, header, err.to_string());
+					// The sub-items read so far are this merit's, not the next one's.
+					if sub.is_none() {
+						children.clear();
+					}
+					continue;
+				}
+			}
 		};
 
 		let mut prerequisites: Vec<Prerequisite> = raw_item
@@ -95,7 +120,16 @@ pub fn parse_merits(info: &BookInfo, section: &Section) -> Result<Vec<MeritItem>
 		}
 
 		let prerequisites = Prerequisites::from(prerequisites);
-		let dot_rating = DotRange::from_str(cost.as_str())?;
+		let dot_rating = match DotRange::from_str(cost.as_str()) {
+			Ok(dot_rating) => dot_rating,
+			Err(err) => {
+				report.error(section, header, format!("Invalid dot rating: {err}"));
+				if sub.is_none() {
+					children.clear();
+				}
+				continue;
+			}
+		};
 
 		if sub.is_some() {
 			children.push(MeritSubItem {
@@ -114,7 +148,7 @@ pub fn parse_merits(info: &BookInfo, section: &Section) -> Result<Vec<MeritItem>
 			out.push(MeritItem {
 				name,
 				reference,
-				description: to_paragraphs(raw_item.get(None)),
+				description: to_paragraphs(raw_item.get(None), hyphenation),
 				effects: raw_item.take(Some(ItemProp::Effects)),
 				inner: Merit {
 					dot_rating,
@@ -130,7 +164,7 @@ pub fn parse_merits(info: &BookInfo, section: &Section) -> Result<Vec<MeritItem>
 		}
 	}
 
-	Ok(out)
+	out
 }
 
 fn process_tags(captures: &Captures<'_>) -> Result<Vec<MeritTag>> {
@@ -1,72 +1,285 @@
-use std::{collections::HashMap, ops::Range};
+use std::{collections::BTreeMap, ops::Range};
 
-use anyhow::Result;
-use cofd_meta::PageKind;
 use cofd_schema::{
 	book::{Book, BookInfo, BookReference},
-	item::gift::GiftKind,
+	item::Item,
 };
 use convert_case::{Case, Casing};
 use regex::Captures;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+mod demon;
+mod discipline;
 mod gift;
 mod item;
 mod merit;
+mod paragraph;
+mod registry;
+mod report;
+mod spell;
+mod table;
 
-use self::{gift::parse_gifts, merit::parse_merits};
-use crate::source::Section;
+pub use self::{
+	paragraph::Hyphenation,
+	registry::{ParserRegistry, SectionParser},
+	report::{Diagnostic, ParseReport, Severity},
+};
+use crate::{
+	schema::{
+		ArmorItem, CipherItem, DevotionItem, DisciplinePowerItem, EmbedItem, ExploitItem, Note,
+		NoteItem, WeaponItem,
+	},
+	source::{Section, SectionTable},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PdfExtract {
 	pub info: BookInfo,
 	pub sections: Vec<Section>,
-	// errors: Vec<Error>,
+	/// The book's spellings of words broken across lines at a hyphen, from its meta.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub hyphenation: Vec<String>,
+}
+
+/// Everything parsed out of a book.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParsedBook {
+	pub book: Book,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub disciplines: Vec<DisciplinePowerItem>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub devotions: Vec<DevotionItem>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub embeds: Vec<EmbedItem>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub exploits: Vec<ExploitItem>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub ciphers: Vec<CipherItem>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub weapons: Vec<WeaponItem>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub armor: Vec<ArmorItem>,
+	/// Tables from `PageKind::Table(TableKind::Other)` sections, keyed by section name.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub tables: BTreeMap<String, Vec<SectionTable>>,
+	/// Sidebars from sections that keep them as notes.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub notes: Vec<NoteItem>,
+	/// Items from `PageKind::Custom` sections, keyed by the custom kind's name.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub custom: BTreeMap<String, Vec<serde_json::Value>>,
+	pub report: ParseReport,
+	/// How words broken across lines are joined, learned from the book being parsed.
+	#[serde(skip)]
+	pub hyphenation: Hyphenation,
+}
+
+impl ParsedBook {
+	#[must_use]
+	pub fn new(info: BookInfo) -> Self {
+		Self {
+			book: Book::from(info),
+			disciplines: Vec::new(),
+			devotions: Vec::new(),
+			embeds: Vec::new(),
+			exploits: Vec::new(),
+			ciphers: Vec::new(),
+			weapons: Vec::new(),
+			armor: Vec::new(),
+			tables: BTreeMap::new(),
+			notes: Vec::new(),
+			custom: BTreeMap::new(),
+			report: ParseReport::default(),
+			hyphenation: Hyphenation::default(),
+		}
+	}
+
+	pub fn push_custom(&mut self, kind: &str, item: &impl Serialize) -> serde_json::Result<()> {
+		self.custom
+			.entry(kind.to_owned())
+			.or_default()
+			.push(serde_json::to_value(item)?);
+		Ok(())
+	}
+
+	pub fn custom<T: DeserializeOwned>(&self, kind: &str) -> serde_json::Result<Vec<T>> {
+		self.custom
+			.get(kind)
+			.into_iter()
+			.flatten()
+			.map(T::deserialize)
+			.collect()
+	}
 }
 
 impl PdfExtract {
-	#[warn(clippy::match_wildcard_for_single_variants)]
-	pub fn parse(self) -> Result<Book> {
-		let mut parse = Book::from(self.info);
+	/// Parses every section, collecting recoverable problems instead of failing the whole book.
+	#[must_use]
+	pub fn parse(self) -> ParsedBook {
+		self.parse_with(&ParserRegistry::default())
+	}
+
+	/// Like [`Self::parse`], but with the given set of parsers.
+	#[must_use]
+	pub fn parse_with(self, parsers: &ParserRegistry) -> ParsedBook {
+		let mut parse = ParsedBook::new(self.info);
+		parse.hyphenation = Hyphenation::new(
+			&self.hyphenation,
+			self.sections.iter().map(|section| section.extract.as_str()),
+		);
 
 		for section in self.sections {
-			match &section.kind {
-				PageKind::Merit(_) => parse
-					.merits
-					.extend(parse_merits(&parse.info, &section).unwrap()),
-				// PageKind::MageSpell => parse.mage_spells.extend(vec.into_iter().map(|i| match i {
-				// 	_ => unreachable!(),
-				// })),
-				PageKind::Gift(kind) => match kind {
-					GiftKind::Moon => {
-						// parse.moon_gifts.extend(todo!())
-					}
-					GiftKind::Shadow | GiftKind::Wolf => {
-						parse.gifts.extend(parse_gifts(&parse.info, &section)?);
-					}
-				},
-				_ => {}
+			let notes = parse_notes(
+				&parse.book.info,
+				&parse.hyphenation,
+				&section,
+				&mut parse.report,
+			);
+			parse.notes.extend(notes);
+			report_ocr(&section, &mut parse.report);
+			if section.printed_pages.is_empty() {
+				parse.report.warning(
+					&section,
+					"",
+					"No printed page numbers found for the section's pages, so its references are to page 0",
+				);
+			}
+			for (page, reason) in &section.unreadable {
+				parse.report.page_error(
+					&section,
+					*page,
+					format!("Page couldn't be read: {reason}"),
+				);
+			}
+
+			if let Some(parser) = parsers.get(&section.kind) {
+				parser.parse(&section, &mut parse);
+			} else {
+				parse.report.warning(
+					&section,
+					"",
+					format!("No parser registered for {:?} sections", section.kind),
+				);
 			}
 		}
-		parse.merits.sort_by(|a, b| a.name.cmp(&b.name));
-		parse.mage_spells.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.book.merits.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.book.mage_spells.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.devotions.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.embeds.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.exploits.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.ciphers.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.weapons.sort_by(|a, b| a.name.cmp(&b.name));
+		parse.armor.sort_by(|a, b| a.name.cmp(&b.name));
 
-		Ok(parse)
+		parse
 	}
 }
 
+/// Warns about a section with lines read with OCR, quoting the line OCR was least sure of.
+fn report_ocr(section: &Section, report: &mut ParseReport) {
+	let ocr: Vec<_> = section
+		.locations
+		.iter()
+		.filter_map(|loc| loc.confidence.map(|confidence| (loc, confidence)))
+		.collect();
+	let Some((worst, _)) = ocr.iter().min_by(|a, b| a.1.total_cmp(&b.1)) else {
+		return;
+	};
+
+	#[allow(clippy::cast_precision_loss)]
+	let mean = ocr.iter().map(|(_, confidence)| confidence).sum::<f32>() / ocr.len() as f32;
+	report.warning(
+		section,
+		&section.original[worst.range.clone()],
+		format!(
+			"{} lines read with OCR, with a mean confidence of {:.0}%",
+			ocr.len(),
+			mean * 100.0
+		),
+	);
+}
+
+fn parse_notes(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<NoteItem> {
+	section
+		.sidebars
+		.iter()
+		.map(|sidebar| {
+			let lines: Vec<String> = sidebar.text.split('\n').map(ToOwned::to_owned).collect();
+
+			Item {
+				name: sidebar.title.clone(),
+				reference: book_reference(
+					info,
+					section,
+					Some(sidebar.page),
+					&sidebar.title,
+					report,
+				),
+				description: paragraph::to_paragraphs(&lines, hyphenation),
+				effects: Vec::new(),
+				inner: Note {
+					section: section.name.clone(),
+				},
+			}
+		})
+		.collect()
+}
+
 fn get_book_reference(
 	captures: &Captures<'_>,
 	section: &Section,
 	info: &BookInfo,
+	report: &mut ParseReport,
 ) -> BookReference {
-	let page = if let Some(match_) = captures.get(0) {
-		let orig_pos = section.original.find(match_.as_str()).unwrap();
-		get_page_number(&section.page_ranges, orig_pos)
-	} else {
-		unreachable!()
+	let snippet = captures.get(0).map_or("", |found| found.as_str());
+
+	text_reference(snippet, section, info, report)
+}
+
+/// A reference to the page `snippet`, a slice of the section's text, is on.
+fn text_reference(
+	snippet: &str,
+	section: &Section,
+	info: &BookInfo,
+	report: &mut ParseReport,
+) -> BookReference {
+	let page = section
+		.find_original(snippet)
+		.and_then(|pos| section.page_at(pos));
+
+	book_reference(info, section, page, snippet, report)
+}
+
+/// A reference to the printed number of PDF page `page`, reporting it when there's none.
+fn book_reference(
+	info: &BookInfo,
+	section: &Section,
+	page: Option<usize>,
+	snippet: &str,
+	report: &mut ParseReport,
+) -> BookReference {
+	let Some(page) = page else {
+		report.warning(section, snippet, "Can't tell which page this is on");
+		return BookReference(info.id, 0);
 	};
-	BookReference(info.id, *page)
+	let printed = section.printed_page(page).unwrap_or_else(|| {
+		// A section without any page numbers is reported once, in `parse_with`.
+		if !section.printed_pages.is_empty() {
+			report.warning(
+				section,
+				snippet,
+				format!("PDF page {page} has no printed page number"),
+			);
+		}
+		0
+	});
+
+	BookReference(info.id, printed)
 }
 
 #[must_use]
@@ -76,80 +289,21 @@ pub fn starts_with_one(str: &str, char_p: char) -> bool {
 	})
 }
 
-mod paragraph {
-	use cofd_schema::DOT_CHAR;
-
-	const PUNCTUATION: [char; 4] = ['.', ':', '!', '?'];
-
-	fn trim_line(line: &str) -> &str {
-		let line = line.trim_start();
-		let line = if line.ends_with("God-") {
-			line
-		} else if line.ends_with('-') {
-			line.trim_end_matches('-')
-		} else {
-			line
-		};
-		line
-	}
-
-	pub fn to_paragraphs(lines: &[String]) -> Vec<String> {
-		if lines.len() == 1 {
-			let line = lines.first().unwrap().trim();
-
-			vec![line.to_owned()]
-		} else {
-			let mut paragraphs = Vec::new();
-			let mut paragraph = String::new();
-
-			let count = lines
-				.iter()
-				.filter(|l| l.starts_with('\t') && !l.starts_with(&format!("\t{DOT_CHAR}")))
-				.count();
-			let too_many_tabs = count > (lines.len() / 2);
-
-			for line in lines {
-				let tab = line.starts_with('\t');
-
-				if (!too_many_tabs && tab) && !paragraph.is_empty() {
-					paragraphs.push(paragraph.trim().to_owned());
-					paragraph = String::new();
-				}
-
-				paragraph.push_str(trim_line(line.as_str()));
-
-				if (too_many_tabs && !tab) && line.trim().ends_with(|c| PUNCTUATION.contains(&c)) {
-					paragraphs.push(paragraph.trim().to_owned());
-					paragraph = String::new();
-				}
-			}
-
-			if !paragraph.is_empty() && !paragraph.eq_ignore_ascii_case("roll results") {
-				paragraphs.push(paragraph.trim().to_owned());
-			}
-
-			paragraphs
-		}
-	}
+fn get_body(str_pos: &mut usize, span: &str, captures: &Captures<'_>) -> Vec<String> {
+	get_body_after(str_pos, span, captures.get(0).unwrap().range())
 }
 
-fn get_body(str_pos: &mut usize, span: &str, captures: &Captures<'_>) -> Vec<String> {
-	let body = span[captures.get(0).unwrap().end()..*str_pos]
+/// Like [`get_body`], but the body starts right after `header` rather than after the whole match.
+fn get_body_after(str_pos: &mut usize, span: &str, header: Range<usize>) -> Vec<String> {
+	let body = span[header.end..*str_pos]
 		.split('\n')
 		.filter_map(filter_normalize)
 		.collect();
-	*str_pos = captures.get(0).unwrap().start();
+	*str_pos = header.start;
 
 	body
 }
 
-fn get_page_number(page_ranges: &HashMap<usize, Range<usize>>, pos: usize) -> &usize {
-	page_ranges
-		.iter()
-		.find(|(_, range)| range.contains(&pos))
-		.map_or(&0, |(p, _)| p)
-}
-
 fn parse_name(captures: &Captures<'_>) -> String {
 	let name = normalize(captures.name("name").map_or("", |f| f.as_str().trim())).replace('\t', "");
 
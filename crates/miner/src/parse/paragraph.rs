@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use cofd_schema::DOT_CHAR;
+
+const PUNCTUATION: [char; 4] = ['.', ':', '!', '?'];
+
+/// Decides whether a word broken across two lines at a hyphen keeps the hyphen when the
+/// lines are joined, like `God-Machine`, or loses it, like `sleepwalker`.
+#[derive(Clone, Debug, Default)]
+pub struct Hyphenation {
+	/// Spellings given for the book, keyed by the word without hyphens, lowercased.
+	spellings: HashMap<String, String>,
+	/// How often each word turns up within a line of the book's text, lowercased.
+	counts: HashMap<String, usize>,
+}
+
+impl Hyphenation {
+	/// Learns the book's spellings from `texts`, with `spellings` overriding them.
+	pub fn new<'a>(spellings: &[String], texts: impl IntoIterator<Item = &'a str>) -> Self {
+		let mut counts = HashMap::new();
+		for line in texts.into_iter().flat_map(str::lines) {
+			let mut words: Vec<&str> = line.split_whitespace().collect();
+			// The last word of a line ending in a hyphen is only the start of a word.
+			if line.trim_end().ends_with('-') {
+				words.pop();
+			}
+
+			for word in words {
+				let word = trim_word(word);
+				if !word.is_empty() {
+					*counts.entry(word.to_lowercase()).or_default() += 1;
+				}
+			}
+		}
+
+		Self {
+			spellings: spellings
+				.iter()
+				.map(|spelling| {
+					(
+						spelling.replace('-', "").to_lowercase(),
+						spelling.to_lowercase(),
+					)
+				})
+				.collect(),
+			counts,
+		}
+	}
+
+	/// Whether `head` and `tail` are joined as `head-tail` rather than `headtail`.
+	///
+	/// A spelling given for the book decides first. Otherwise a capitalized `tail` keeps the
+	/// hyphen, and then whichever form the book uses more often wins, joining the word on a
+	/// tie. A compound that's only ever broken across lines needs a spelling.
+	#[must_use]
+	pub fn keeps_hyphen(&self, head: &str, tail: &str) -> bool {
+		let joined = format!("{head}{tail}").to_lowercase();
+		let hyphenated = format!("{head}-{tail}").to_lowercase();
+
+		if let Some(spelling) = self
+			.spellings
+			.get(&joined.replace('-', ""))
+			.filter(|spelling| **spelling == hyphenated || **spelling == joined)
+		{
+			return *spelling == hyphenated;
+		}
+		if tail.starts_with(char::is_uppercase) {
+			return true;
+		}
+
+		let count = |word: &str| self.counts.get(word).copied().unwrap_or(0);
+		count(&hyphenated) > count(&joined)
+	}
+}
+
+/// The word without the punctuation around it.
+fn trim_word(word: &str) -> &str {
+	word.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+/// Appends `line` to `paragraph`, resolving a word broken at a hyphen between them.
+fn push_line(paragraph: &mut String, line: &str, hyphenation: &Hyphenation) {
+	let line = line.trim_start();
+
+	if let Some(rest) = paragraph.strip_suffix('-') {
+		let head = trim_word(rest.rsplit(char::is_whitespace).next().unwrap_or_default());
+		let tail = trim_word(line.split(char::is_whitespace).next().unwrap_or_default());
+
+		if rest.ends_with(char::is_alphabetic)
+			&& !tail.is_empty()
+			&& !hyphenation.keeps_hyphen(head, tail)
+		{
+			paragraph.pop();
+		}
+	}
+
+	paragraph.push_str(line);
+}
+
+pub fn to_paragraphs(lines: &[String], hyphenation: &Hyphenation) -> Vec<String> {
+	if lines.len() == 1 {
+		let line = lines.first().unwrap().trim();
+
+		vec![line.to_owned()]
+	} else {
+		let mut paragraphs = Vec::new();
+		let mut paragraph = String::new();
+
+		let count = lines
+			.iter()
+			.filter(|l| l.starts_with('\t') && !l.starts_with(&format!("\t{DOT_CHAR}")))
+			.count();
+		let too_many_tabs = count > (lines.len() / 2);
+
+		for line in lines {
+			let tab = line.starts_with('\t');
+
+			if (!too_many_tabs && tab) && !paragraph.is_empty() {
+				paragraphs.push(paragraph.trim().to_owned());
+				paragraph = String::new();
+			}
+
+			push_line(&mut paragraph, line, hyphenation);
+
+			if (too_many_tabs && !tab) && line.trim().ends_with(|c| PUNCTUATION.contains(&c)) {
+				paragraphs.push(paragraph.trim().to_owned());
+				paragraph = String::new();
+			}
+		}
+
+		if !paragraph.is_empty() && !paragraph.eq_ignore_ascii_case("roll results") {
+			paragraphs.push(paragraph.trim().to_owned());
+		}
+
+		paragraphs
+	}
+}
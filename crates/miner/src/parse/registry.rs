@@ -0,0 +1,232 @@
+use cofd_meta::{PageKind, TableKind};
+use cofd_schema::item::gift::GiftKind;
+
+use super::{
+	demon::{parse_ciphers, parse_embeds, parse_exploits},
+	discipline::{parse_devotions, parse_disciplines},
+	gift::{parse_gifts, parse_moon_gifts},
+	merit::parse_merits,
+	spell::parse_spells,
+	table::{parse_armor, parse_tables, parse_weapons},
+	ParsedBook,
+};
+use crate::source::Section;
+
+/// Turns the text of one kind of section into items.
+///
+/// Implement this to extract content the miner doesn't know about, typically for sections
+/// marked `PageKind::Custom(..)` in the meta, and add it to a [`ParserRegistry`].
+pub trait SectionParser: Send + Sync {
+	/// Whether this parser handles sections of the given kind.
+	fn handles(&self, kind: &PageKind) -> bool;
+
+	/// Parses `section` into `out`.
+	///
+	/// Recoverable problems go to `out.report`; a parser should skip what it can't read
+	/// rather than give up on the whole section.
+	fn parse(&self, section: &Section, out: &mut ParsedBook);
+}
+
+/// The set of [`SectionParser`]s used to parse a [`super::PdfExtract`].
+///
+/// When several parsers handle the same kind, the one registered last wins, so the
+/// built-in parsers can be replaced.
+pub struct ParserRegistry {
+	parsers: Vec<Box<dyn SectionParser>>,
+}
+
+impl ParserRegistry {
+	/// A registry with no parsers at all, not even the built-in ones.
+	#[must_use]
+	pub fn empty() -> Self {
+		Self {
+			parsers: Vec::new(),
+		}
+	}
+
+	pub fn register(&mut self, parser: impl SectionParser + 'static) -> &mut Self {
+		self.parsers.push(Box::new(parser));
+		self
+	}
+
+	#[must_use]
+	pub fn with(mut self, parser: impl SectionParser + 'static) -> Self {
+		self.register(parser);
+		self
+	}
+
+	#[must_use]
+	pub fn get(&self, kind: &PageKind) -> Option<&dyn SectionParser> {
+		self.parsers
+			.iter()
+			.rev()
+			.find(|parser| parser.handles(kind))
+			.map(AsRef::as_ref)
+	}
+}
+
+impl Default for ParserRegistry {
+	/// A registry with the built-in parsers.
+	fn default() -> Self {
+		Self::empty()
+			.with(MeritParser)
+			.with(GiftParser)
+			.with(MoonGiftParser)
+			.with(SpellParser)
+			.with(DisciplineParser)
+			.with(DevotionParser)
+			.with(EmbedParser)
+			.with(ExploitParser)
+			.with(CipherParser)
+			.with(WeaponParser)
+			.with(ArmorParser)
+			.with(TableParser)
+	}
+}
+
+pub struct MeritParser;
+impl SectionParser for MeritParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Merit(_))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_merits(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.book.merits.extend(items);
+	}
+}
+
+pub struct GiftParser;
+impl SectionParser for GiftParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Gift(GiftKind::Shadow | GiftKind::Wolf))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_gifts(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.book.gifts.extend(items);
+	}
+}
+
+pub struct MoonGiftParser;
+impl SectionParser for MoonGiftParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Gift(GiftKind::Moon))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_moon_gifts(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.book.moon_gifts.extend(items);
+	}
+}
+
+pub struct SpellParser;
+impl SectionParser for SpellParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::MageSpell)
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_spells(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.book.mage_spells.extend(items);
+	}
+}
+
+pub struct DisciplineParser;
+impl SectionParser for DisciplineParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Discipline(_))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_disciplines(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.disciplines.extend(items);
+	}
+}
+
+pub struct DevotionParser;
+impl SectionParser for DevotionParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Devotion)
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_devotions(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.devotions.extend(items);
+	}
+}
+
+pub struct EmbedParser;
+impl SectionParser for EmbedParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Embed(_))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_embeds(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.embeds.extend(items);
+	}
+}
+
+pub struct ExploitParser;
+impl SectionParser for ExploitParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Exploit)
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_exploits(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.exploits.extend(items);
+	}
+}
+
+pub struct CipherParser;
+impl SectionParser for CipherParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Cipher)
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_ciphers(&out.book.info, &out.hyphenation, section, &mut out.report);
+		out.ciphers.extend(items);
+	}
+}
+
+pub struct WeaponParser;
+impl SectionParser for WeaponParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Table(TableKind::Weapon))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_weapons(&out.book.info, section, &mut out.report);
+		out.weapons.extend(items);
+	}
+}
+
+pub struct ArmorParser;
+impl SectionParser for ArmorParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Table(TableKind::Armor))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let items = parse_armor(&out.book.info, section, &mut out.report);
+		out.armor.extend(items);
+	}
+}
+
+pub struct TableParser;
+impl SectionParser for TableParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Table(TableKind::Other))
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		let tables = parse_tables(section, &mut out.report);
+		out.tables
+			.entry(section.name.clone())
+			.or_default()
+			.extend(tables);
+	}
+}
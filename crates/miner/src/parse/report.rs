@@ -0,0 +1,137 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{backend::Rect, source::Section};
+
+const SNIPPET_LEN: usize = 80;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+	Warning,
+	Error,
+}
+
+/// A problem found while parsing a section, with enough context to find it in the PDF.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub section: String,
+	/// The zero-based PDF page.
+	pub page: Option<usize>,
+	/// The number printed on that page.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub printed_page: Option<usize>,
+	/// The region of the page the snippet starts in.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub bbox: Option<Rect>,
+	pub snippet: String,
+	pub reason: String,
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let severity = match self.severity {
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		};
+		write!(f, "{severity}: {}", self.section)?;
+		match (self.printed_page, self.page) {
+			(Some(printed), Some(page)) if printed != page => {
+				write!(f, " (page {printed}, PDF page {page})")?;
+			}
+			(_, Some(page)) => write!(f, " (page {page})")?,
+			_ => {}
+		}
+		write!(f, ": {}", self.reason)?;
+		if !self.snippet.is_empty() {
+			write!(f, "\n\t{}", self.snippet)?;
+		}
+		Ok(())
+	}
+}
+
+/// Everything that went wrong while turning a [`super::PdfExtract`] into a [`super::ParsedBook`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ParseReport {
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseReport {
+	pub fn warning(&mut self, section: &Section, snippet: &str, reason: impl Into<String>) {
+		self.push(Severity::Warning, section, snippet, reason.into());
+	}
+
+	pub fn error(&mut self, section: &Section, snippet: &str, reason: impl Into<String>) {
+		self.push(Severity::Error, section, snippet, reason.into());
+	}
+
+	/// Reports a page of `section` that couldn't be read at all.
+	pub fn page_error(&mut self, section: &Section, page: usize, reason: impl Into<String>) {
+		self.diagnostics.push(Diagnostic {
+			severity: Severity::Error,
+			section: section.name.clone(),
+			page: Some(page),
+			printed_page: section.printed_page(page),
+			bbox: None,
+			snippet: String::new(),
+			reason: reason.into(),
+		});
+	}
+
+	fn push(&mut self, severity: Severity, section: &Section, snippet: &str, reason: String) {
+		// An empty snippet points at the whole section, not at its first page.
+		let pos = if snippet.trim().is_empty() {
+			None
+		} else {
+			section.find_original(snippet)
+		};
+		let location = pos.and_then(|pos| section.locate(pos));
+		let page = pos.and_then(|pos| section.page_at(pos));
+
+		let snippet = snippet.trim();
+		let snippet = match snippet.char_indices().nth(SNIPPET_LEN) {
+			Some((i, _)) => format!("{}…", &snippet[..i]),
+			None => snippet.to_owned(),
+		};
+
+		self.diagnostics.push(Diagnostic {
+			severity,
+			section: section.name.clone(),
+			page,
+			printed_page: page.and_then(|page| section.printed_page(page)),
+			bbox: location.map(|loc| loc.bbox),
+			snippet,
+			reason,
+		});
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.diagnostics.is_empty()
+	}
+
+	#[must_use]
+	pub fn has_errors(&self) -> bool {
+		self.errors().next().is_some()
+	}
+
+	pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+		self.diagnostics
+			.iter()
+			.filter(|d| d.severity == Severity::Error)
+	}
+
+	pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+		self.diagnostics
+			.iter()
+			.filter(|d| d.severity == Severity::Warning)
+	}
+
+	/// Diagnostics reported for the section with the given name.
+	pub fn section<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Diagnostic> {
+		self.diagnostics.iter().filter(move |d| d.section == name)
+	}
+}
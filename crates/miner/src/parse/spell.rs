@@ -0,0 +1,171 @@
+use std::str::FromStr;
+
+use cofd_schema::{
+	book::SpellItem,
+	dot_range::dots_to_num,
+	item::{
+		spell::{Practice, PrimaryFactor, ReachEffect, Spell},
+		Item,
+	},
+	prelude::BookInfo,
+	template::mage::Arcanum,
+	traits::skill::Skill,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{get_body, get_book_reference, item::ItemProp, parse_name, report::ParseReport};
+use crate::{
+	parse::{
+		item::RawItem,
+		paragraph::{to_paragraphs, Hyphenation},
+	},
+	source::Section,
+};
+
+static SPELL_HEADER_REGEX: Lazy<Regex> = Lazy::new(|| {
+	Regex::new(
+		r"(?xm)
+		^\t*
+		(?<name> [^\s(] [^\n(]+? )                                               # Name
+		\s?
+		\(
+			(?<arcanum> Death|Fate|Forces|Life|Matter|Mind|Prime|Space|Spirit|Time ) # Arcanum
+			\s?
+			(?<level> •{1,5} )                                                   # Level
+			[^\n)]*                                                              # Additional Arcana
+		\)
+		\s?
+		$
+	",
+	)
+	.unwrap()
+});
+
+static REACH_REGEX: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"^\+\s?(?<reach>\d)\s+Reach:\s?(?<effect>.*)$").unwrap());
+
+pub fn parse_spells(
+	info: &BookInfo,
+	hyphenation: &Hyphenation,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<SpellItem> {
+	let mut out = Vec::new();
+	let mut str_pos = section.extract.len();
+
+	for captures in SPELL_HEADER_REGEX
+		.captures_iter(&section.extract)
+		.collect::<Vec<_>>()
+		.into_iter()
+		.rev()
+	{
+		let header = captures.get(0).unwrap().as_str();
+
+		let name = parse_name(&captures);
+		let reference = get_book_reference(&captures, section, info, report);
+		let body = get_body(&mut str_pos, &section.extract, &captures);
+
+		let arcanum = captures.name("arcanum").unwrap().as_str();
+		let Ok(arcanum) = Arcanum::from_str(arcanum) else {
+			report.error(section, header, format!("Unknown arcanum \"{arcanum}\""));
+			continue;
+		};
+		let level = dots_to_num(captures.name("level").unwrap().as_str()).unwrap_or(0);
+
+		let split = body
+			.iter()
+			.position(|line| REACH_REGEX.is_match(line.trim_start()))
+			.unwrap_or(body.len());
+		let (body, reach_lines) = body.split_at(split);
+
+		let mut raw_item = {
+			let v: Vec<&str> = body.iter().map(String::as_str).collect();
+			match RawItem::parse(v, hyphenation) {
+				Ok(raw_item) => raw_item,
+				Err(err) => {
+					report.error(section, header, err.to_string());
+					continue;
+				}
+			}
+		};
+
+		let practice = raw_item.take(Some(ItemProp::Practice)).concat();
+		let Ok(practice) = Practice::from_str(practice.trim()) else {
+			report.error(section, header, format!("Unknown practice \"{practice}\""));
+			continue;
+		};
+		let primary_factor = raw_item.take(Some(ItemProp::PrimaryFactor)).concat();
+		let Ok(primary_factor) = PrimaryFactor::from_str(primary_factor.trim()) else {
+			report.error(
+				section,
+				header,
+				format!("Unknown primary factor \"{primary_factor}\""),
+			);
+			continue;
+		};
+
+		let suggested_rote_skills = raw_item
+			.take(Some(ItemProp::SuggestedRoteSkills))
+			.concat()
+			.split([',', ';'])
+			.flat_map(|s| s.split(" or "))
+			.map(|s| s.trim().trim_end_matches('.'))
+			.filter(|s| !s.is_empty())
+			.filter_map(|skill| {
+				let parsed = Skill::from_str(skill).ok();
+				if parsed.is_none() {
+					report.warning(section, header, format!("Unknown rote skill \"{skill}\""));
+				}
+				parsed
+			})
+			.collect();
+
+		out.push(Item {
+			name,
+			reference,
+			description: raw_item.take(None),
+			effects: raw_item.take(Some(ItemProp::Effects)),
+			inner: Spell {
+				arcanum,
+				level,
+				practice,
+				primary_factor,
+				withstand: raw_item.take(Some(ItemProp::Withstand)).into_iter().next(),
+				suggested_rote_skills,
+				reach: parse_reach(reach_lines, hyphenation),
+				cost: raw_item.take(Some(ItemProp::Cost)),
+			},
+		});
+	}
+
+	out
+}
+
+fn parse_reach(lines: &[String], hyphenation: &Hyphenation) -> Vec<ReachEffect> {
+	let mut reach: Vec<(u8, Vec<String>)> = Vec::new();
+
+	for line in lines {
+		if let Some(captures) = REACH_REGEX.captures(line.trim_start()) {
+			let cost = captures
+				.name("reach")
+				.unwrap()
+				.as_str()
+				.parse()
+				.unwrap_or(1);
+			let effect = captures.name("effect").unwrap().as_str().to_owned();
+
+			reach.push((cost, vec![effect]));
+		} else if let Some((_, effect)) = reach.last_mut() {
+			effect.push(line.trim_start().to_owned());
+		}
+	}
+
+	reach
+		.into_iter()
+		.map(|(reach, effect)| ReachEffect {
+			reach,
+			effect: to_paragraphs(&effect, hyphenation).join("\n"),
+		})
+		.collect()
+}
@@ -0,0 +1,214 @@
+use cofd_meta::{PageKind, TableKind};
+use cofd_schema::{dot_range::dots_to_num, item::Item, prelude::BookInfo, DOT_CHAR};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{book_reference, report::ParseReport};
+use crate::{
+	schema::{Armor, ArmorItem, ArmorRating, Weapon, WeaponItem},
+	source::{Section, SectionTable},
+};
+
+static NUMBER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[+-]?\d+").unwrap());
+static RATING_REGEX: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"^(?<general>\d+)\s*/\s*(?<ballistic>\d+)").unwrap());
+
+const NAME: &[&str] = &["type", "weapon", "armor", "name", "item"];
+const DAMAGE: &[&str] = &["damage", "dmg"];
+const INITIATIVE: &[&str] = &["initiative", "init"];
+const STRENGTH: &[&str] = &["strength", "str"];
+const SIZE: &[&str] = &["size"];
+const AVAILABILITY: &[&str] = &["availability", "avail", "cost"];
+const RANGE: &[&str] = &["range"];
+const CLIP: &[&str] = &["clip", "capacity"];
+const TAGS: &[&str] = &["special", "tags", "notes"];
+const RATING: &[&str] = &["rating"];
+const DEFENSE: &[&str] = &["defense", "def"];
+const SPEED: &[&str] = &["speed"];
+const COVERAGE: &[&str] = &["coverage"];
+
+/// One body row of a table, read by column header.
+struct Row<'a> {
+	table: &'a SectionTable,
+	cells: &'a [String],
+}
+
+impl Row<'_> {
+	fn name(&self) -> &str {
+		let i = self.table.table.column(NAME).unwrap_or(0);
+		self.cells[i].as_str()
+	}
+
+	fn get(&self, names: &[&str]) -> Option<&str> {
+		self.table
+			.table
+			.column(names)
+			.map(|i| self.cells[i].as_str())
+			.filter(|cell| !cell.is_empty())
+	}
+
+	fn list(&self, names: &[&str]) -> Vec<String> {
+		self.get(names)
+			.filter(|cell| !is_dash(cell))
+			.map(|cell| cell.split(", ").map(ToOwned::to_owned).collect())
+			.unwrap_or_default()
+	}
+
+	fn modifier(&self, names: &[&str]) -> Result<i8, String> {
+		let cell = self.get(names).unwrap_or_default();
+		if is_dash(cell) {
+			return Ok(0);
+		}
+
+		let cell = cell.replace(['−', '–'], "-");
+		NUMBER_REGEX
+			.find(&cell)
+			.and_then(|number| number.as_str().trim_start_matches('+').parse().ok())
+			.ok_or_else(|| format!("Can't read {} \"{cell}\"", names[0]))
+	}
+
+	fn rating(&self, names: &[&str]) -> Result<u8, String> {
+		let cell = self.get(names).unwrap_or_default();
+		if cell.contains(DOT_CHAR) {
+			return dots_to_num(cell).ok_or_else(|| format!("Can't read {} \"{cell}\"", names[0]));
+		}
+
+		u8::try_from(self.modifier(names)?)
+			.map_err(|_| format!("{} \"{cell}\" is negative", names[0]))
+	}
+}
+
+/// Calls `read` for each body row of each table of the section, checking first that the
+/// table has all the `required` columns.
+fn parse_rows<T>(
+	info: &BookInfo,
+	section: &Section,
+	report: &mut ParseReport,
+	required: &[&[&str]],
+	read: impl Fn(&Row<'_>) -> Result<T, String>,
+) -> Vec<Item<T>> {
+	let mut out = Vec::new();
+
+	if section.tables.is_empty() {
+		report.warning(section, "", "No tables found in section");
+	}
+
+	for table in &section.tables {
+		if let Some([missing, ..]) = required
+			.iter()
+			.find(|names| table.table.column(names).is_none())
+		{
+			report.error(
+				section,
+				&table.table.header.join(" "),
+				format!("Table on page {} has no {missing} column", table.page),
+			);
+			continue;
+		}
+
+		report_cut_off(section, table, report);
+		for cells in &table.table.rows {
+			let row = Row { table, cells };
+			match read(&row) {
+				Ok(inner) => out.push(Item {
+					name: row.name().to_owned(),
+					reference: book_reference(info, section, Some(table.page), row.name(), report),
+					description: Vec::new(),
+					effects: Vec::new(),
+					inner,
+				}),
+				Err(reason) => report.warning(section, row.name(), reason),
+			}
+		}
+	}
+
+	out
+}
+
+pub fn parse_weapons(
+	info: &BookInfo,
+	section: &Section,
+	report: &mut ParseReport,
+) -> Vec<WeaponItem> {
+	parse_rows(
+		info,
+		section,
+		report,
+		&[DAMAGE, INITIATIVE, STRENGTH, SIZE, AVAILABILITY],
+		|row| {
+			Ok(Weapon {
+				damage: row.modifier(DAMAGE)?,
+				initiative: row.modifier(INITIATIVE)?,
+				strength: row.rating(STRENGTH)?,
+				size: row.rating(SIZE)?,
+				availability: row.rating(AVAILABILITY)?,
+				range: row.get(RANGE).map(ToOwned::to_owned),
+				clip: row.get(CLIP).map(ToOwned::to_owned),
+				tags: row.list(TAGS),
+			})
+		},
+	)
+}
+
+pub fn parse_armor(info: &BookInfo, section: &Section, report: &mut ParseReport) -> Vec<ArmorItem> {
+	parse_rows(
+		info,
+		section,
+		report,
+		&[RATING, STRENGTH, DEFENSE, SPEED, AVAILABILITY],
+		|row| {
+			let rating = row.get(RATING).unwrap_or_default();
+			let rating = RATING_REGEX
+				.captures(rating)
+				.and_then(|captures| {
+					Some(ArmorRating {
+						general: captures["general"].parse().ok()?,
+						ballistic: captures["ballistic"].parse().ok()?,
+					})
+				})
+				.ok_or_else(|| format!("Can't read rating \"{rating}\""))?;
+
+			Ok(Armor {
+				rating,
+				strength: row.rating(STRENGTH)?,
+				defense: row.modifier(DEFENSE)?,
+				speed: row.modifier(SPEED)?,
+				availability: row.rating(AVAILABILITY)?,
+				coverage: row.list(COVERAGE),
+			})
+		},
+	)
+}
+
+/// The tables of a [`TableKind::Other`] section, as they are.
+pub fn parse_tables(section: &Section, report: &mut ParseReport) -> Vec<SectionTable> {
+	if !matches!(section.kind, PageKind::Table(TableKind::Other)) {
+		report.error(section, "", "Section is not a table section");
+		return Vec::new();
+	}
+	if section.tables.is_empty() {
+		report.warning(section, "", "No tables found in section");
+	}
+	for table in &section.tables {
+		report_cut_off(section, table, report);
+	}
+
+	section.tables.clone()
+}
+
+fn report_cut_off(section: &Section, table: &SectionTable, report: &mut ParseReport) {
+	if let Some(row) = &table.table.cut_off {
+		report.warning(
+			section,
+			row,
+			format!(
+				"Table on page {} ends at a row with two cells in one column",
+				table.page
+			),
+		);
+	}
+}
+
+fn is_dash(cell: &str) -> bool {
+	matches!(cell, "—" | "–" | "-" | "n/a" | "N/A")
+}
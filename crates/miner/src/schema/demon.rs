@@ -0,0 +1,38 @@
+use cofd_schema::item::{ActionFields, Item};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Embed {
+	/// The Incarnation the Embed is listed under, which matters when building a Cipher.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub incarnation: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub action: Option<ActionFields>,
+}
+
+pub type EmbedItem = Item<Embed>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Exploit {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub prerequisites: Vec<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub action: Option<ActionFields>,
+}
+
+pub type ExploitItem = Item<Exploit>;
+
+/// A demon's Cipher: the Embeds that are its Interlocks, in order, and the Final Truth they
+/// unlock.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Cipher {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub interlocks: Vec<String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub final_truth: Vec<String>,
+}
+
+pub type CipherItem = Item<Cipher>;
@@ -0,0 +1,45 @@
+use cofd_schema::item::Item;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Weapon {
+	pub damage: i8,
+	pub initiative: i8,
+	pub strength: u8,
+	pub size: u8,
+	/// The cost in dots of Resources.
+	pub availability: u8,
+	/// Short, medium and long range, for ranged weapons.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub range: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub clip: Option<String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tags: Vec<String>,
+}
+
+pub type WeaponItem = Item<Weapon>;
+
+/// Armor rating, as in "2/1".
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmorRating {
+	pub general: u8,
+	pub ballistic: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Armor {
+	pub rating: ArmorRating,
+	pub strength: u8,
+	pub defense: i8,
+	pub speed: i8,
+	/// The cost in dots of Resources.
+	pub availability: u8,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub coverage: Vec<String>,
+}
+
+pub type ArmorItem = Item<Armor>;
@@ -0,0 +1,13 @@
+//! Item types for content that `cofd_schema` has no representation for yet.
+
+mod demon;
+mod equipment;
+mod note;
+mod vampire;
+
+pub use self::{
+	demon::{Cipher, CipherItem, Embed, EmbedItem, Exploit, ExploitItem},
+	equipment::{Armor, ArmorItem, ArmorRating, Weapon, WeaponItem},
+	note::{Note, NoteItem},
+	vampire::{Devotion, DevotionItem, DisciplinePower, DisciplinePowerItem, DisciplineRating},
+};
@@ -0,0 +1,12 @@
+use cofd_schema::item::Item;
+use serde::{Deserialize, Serialize};
+
+/// A sidebar kept out of a section's text, as a note on it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+	/// The name of the section the sidebar was on the pages of.
+	pub section: String,
+}
+
+pub type NoteItem = Item<Note>;
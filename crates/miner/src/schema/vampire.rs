@@ -0,0 +1,33 @@
+use cofd_schema::item::{ActionFields, Item};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DisciplinePower {
+	pub discipline: String,
+	pub level: u8,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub action: Option<ActionFields>,
+}
+
+pub type DisciplinePowerItem = Item<DisciplinePower>;
+
+/// A Discipline at a minimum rating, as in "Auspex ••".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DisciplineRating {
+	pub discipline: String,
+	pub level: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Devotion {
+	pub prerequisites: Vec<DisciplineRating>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub experience_cost: Option<u8>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub action: Option<ActionFields>,
+}
+
+pub type DevotionItem = Item<Devotion>;
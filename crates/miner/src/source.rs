@@ -1,112 +1,507 @@
-use std::{collections::HashMap, ops::Range, path::Path};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	ops::{Range, RangeInclusive},
+	path::Path,
+};
 
 use anyhow::Result;
-use cofd_meta::{Op, PageKind, SectionMeta, SectionRange, SourceMeta};
+use cofd_meta::{printed_page, Op, PageKind, SectionMeta, SectionRange, Sidebars, SourceMeta};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+mod normalize;
+mod offsets;
+
+pub use self::{normalize::Normalizer, offsets::OffsetMap};
 pub use crate::backend::extract_pages;
-use crate::{backend::PdfText, parse::PdfExtract};
+use crate::{
+	backend::{
+		self, join_lines, slice_lines, style_at, FontStyle, PdfLine, PdfPages, Rect, Span, Table,
+		TextBackend, TextDocument,
+	},
+	error::CofDMinerError,
+	parse::PdfExtract,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Section {
+	pub name: String,
 	pub kind: PageKind,
 	pub extract: String,
 	pub original: String,
 	pub page_ranges: HashMap<usize, Range<usize>>,
+	/// The number printed on each of the section's PDF pages that has one, from the book's
+	/// page labels or, without them, from the page numbers found on the pages.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub printed_pages: BTreeMap<usize, usize>,
+	/// Maps offsets in `extract` back to `original`. Required, as there's no telling what an
+	/// extract's offsets are without it.
+	pub offsets: OffsetMap,
+	/// The styled runs of `original`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub styles: Vec<Span>,
+	/// Where each line of `original` came from, in order.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub locations: Vec<Location>,
+	/// The tables on the section's pages that overlap its text.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tables: Vec<SectionTable>,
+	/// The boxed text taken out of the section, when it keeps [`Sidebars::Notes`].
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sidebars: Vec<Sidebar>,
+	/// The section's pages that couldn't be read, with why.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub unreadable: BTreeMap<usize, String>,
+}
+
+/// A box or sidebar on one of a section's pages.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sidebar {
+	pub page: usize,
+	/// The box's first line, which is its heading in the books.
+	pub title: String,
+	/// The rest of the box's lines.
+	pub text: String,
+}
+
+/// A table on one of a section's pages.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionTable {
+	pub page: usize,
+	#[serde(flatten)]
+	pub table: Table,
+}
+
+/// The region of a page a line of a section's text was taken from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+	/// The line's byte range in `original`.
+	pub range: Range<usize>,
+	pub page: usize,
+	pub bbox: Rect,
+	/// How sure OCR was of the line, if it was read with OCR.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub confidence: Option<f32>,
+}
+
+impl Section {
+	/// The style of the text at byte `pos` of `original`.
+	#[must_use]
+	pub fn style_at(&self, pos: usize) -> Option<&FontStyle> {
+		style_at(&self.styles, pos)
+	}
+
+	/// The offset in `original` of byte `pos` of `extract`.
+	#[must_use]
+	pub fn to_original(&self, pos: usize) -> usize {
+		self.offsets.to_original(pos)
+	}
+
+	/// The offset in `original` of `text`, which must be a slice of `extract` or
+	/// `original` to be found exactly. Other text is looked for in `original`.
+	#[must_use]
+	pub fn find_original(&self, text: &str) -> Option<usize> {
+		if let Some(pos) = offset_in(&self.extract, text) {
+			Some(self.to_original(pos))
+		} else {
+			offset_in(&self.original, text).or_else(|| self.original.find(text))
+		}
+	}
+
+	/// The PDF page the text at byte `pos` of `original` is on.
+	#[must_use]
+	pub fn page_at(&self, pos: usize) -> Option<usize> {
+		self.locate(pos).map(|loc| loc.page).or_else(|| {
+			self.page_ranges
+				.iter()
+				.find(|(_, range)| range.contains(&pos))
+				.map(|(page, _)| *page)
+		})
+	}
+
+	/// The number printed on PDF page `index`, if it has one.
+	#[must_use]
+	pub fn printed_page(&self, index: usize) -> Option<usize> {
+		self.printed_pages.get(&index).copied()
+	}
+
+	/// Where on which page the text at byte `pos` of `original` is.
+	#[must_use]
+	pub fn locate(&self, pos: usize) -> Option<&Location> {
+		let i = self.locations.partition_point(|loc| loc.range.end < pos);
+		self.locations
+			.get(i)
+			.filter(|loc| loc.range.start <= pos && pos <= loc.range.end)
+	}
 }
 
 pub fn process_section(
-	pages: &PdfText,
+	pages: &PdfPages,
+	section: &SectionMeta,
+	flag: bool,
+) -> anyhow::Result<Section> {
+	process_section_with(pages, section, flag, &Normalizer::default())
+}
+
+/// Like [`process_section`], but normalizing the text with `normalizer`, which may have a
+/// book's own replacements.
+pub fn process_section_with(
+	pages: &PdfPages,
 	section: &SectionMeta,
 	flag: bool,
+	normalizer: &Normalizer,
 ) -> anyhow::Result<Section> {
-	let pages: PdfText = pages
+	let tables: Vec<SectionTable> = pages
 		.range(section.pages.clone())
-		.map(|(i, p)| (*i, p.clone()))
+		.flat_map(|(i, page)| {
+			page.tables.iter().map(|table| SectionTable {
+				page: *i,
+				table: table.clone(),
+			})
+		})
+		.collect();
+	let unreadable = pages
+		.range(section.pages.clone())
+		.filter_map(|(i, page)| Some((*i, page.error.clone()?)))
+		.collect();
+
+	let pages: BTreeMap<usize, Vec<PdfLine>> = pages
+		.range(section.pages.clone())
+		.map(|(i, p)| (*i, normalizer.normalize_lines(&p.lines)))
 		.collect();
-	// .map(|(page_i, page)| {
-	// 	(
-	// 		*page_i,
-	// 		page
-	// 		.split('\n')
-	// 			.filter(|line| !line.is_empty())
-	// 			.map(str::to_owned)
-	// 			.collect::<Vec<String>>()
-	// 			.join("\n"),
-	// 	)
-	// })
-	// // .map(str::to_owned)
-	// .collect();
 
 	let mut page_ranges = HashMap::new();
 	let mut start = 0;
 	let mut end = 0;
-	for (i, page) in &pages {
-		let page = page.join("\n");
-		end += page.len();
+	for (i, lines) in &pages {
+		end += join_lines(lines).0.len();
 		page_ranges.insert(*i, start..end);
 		start = end;
 	}
 
-	let extract = pages.into_values().flatten().collect::<Vec<String>>();
-	let extract = if flag {
-		extract
-	} else if let Some(range) = &section.range {
-		match range {
-			SectionRange::Range(range) => extract
-				.get(range.clone())
-				.map(ToOwned::to_owned)
-				.unwrap_or(extract),
-			SectionRange::Regex(regex) => {
-				let extract_str = extract.join("\n");
-				regex
-					.captures(&extract_str)
-					.and_then(|c| c.get(1).or_else(|| c.get(0)))
-					.map(|m| m.as_str().split('\n').map(str::to_owned).collect())
-					.unwrap_or(extract)
-			}
-		}
+	let lines: Vec<(usize, PdfLine)> = pages
+		.into_iter()
+		.flat_map(|(i, lines)| lines.into_iter().map(move |line| (i, line)))
+		.collect();
+	let lines = match &section.range {
+		Some(range) if !flag => select_range(lines, range),
+		_ => lines,
+	};
+	let lines: Vec<(usize, PdfLine)> = if section.emphasis {
+		lines
+			.into_iter()
+			.map(|(page, line)| (page, line.with_emphasis()))
+			.collect()
+	} else {
+		lines
+	};
+	let (lines, sidebars) = if flag {
+		(lines, Vec::new())
 	} else {
-		extract
+		split_sidebars(lines, section.sidebars)
 	};
 
-	let original = extract
-		.join("\n")
-		.replace(['‘', '’'], "'")
-		.replace('–', "-");
+	let locations = locate_lines(&lines);
+
+	let (original, styles) = join_lines(lines.iter().map(|(_, line)| line));
+	let tables = tables
+		.into_iter()
+		.filter(|table| {
+			locations
+				.iter()
+				.any(|loc| loc.page == table.page && overlaps(loc.bbox, table.table.bbox))
+		})
+		.collect();
 	let mut extract = original.clone();
+	let mut offsets = OffsetMap::new(original.len());
 
 	if !flag {
-		for op in &section.ops {
-			#[allow(clippy::single_match)]
-			match op {
-				Op::RegexReplace { regex, replace } => {
-					extract = regex.replace_all(&extract, replace).into_owned();
+		for (index, op) in section.ops.iter().enumerate() {
+			apply_op(&mut extract, &mut offsets, op).map_err(|reason| {
+				CofDMinerError::InvalidOp {
+					section: section.name.clone(),
+					index,
+					reason,
 				}
-				_ => {}
-			}
+			})?;
 		}
 	}
 
 	Ok(Section {
+		name: section.name.clone(),
 		original,
 		extract,
 		kind: section.kind.clone(),
 		page_ranges,
+		printed_pages: BTreeMap::new(),
+		offsets,
+		styles,
+		locations,
+		tables,
+		sidebars,
+		unreadable,
 	})
 }
 
+/// The lines of a section's pages that its `range` picks out, or all of them if it
+/// matches nothing.
+fn select_range(lines: Vec<(usize, PdfLine)>, range: &SectionRange) -> Vec<(usize, PdfLine)> {
+	match range {
+		SectionRange::Range(range) => lines
+			.get(range.clone())
+			.map(ToOwned::to_owned)
+			.unwrap_or(lines),
+		SectionRange::Regex(regex) => {
+			let (text, _) = join_lines(lines.iter().map(|(_, line)| line));
+			regex
+				.captures(&text)
+				.and_then(|c| c.get(1).or_else(|| c.get(0)))
+				.map(|m| {
+					slice_lines(lines.iter().map(|(_, line)| line), m.range())
+						.into_iter()
+						.map(|(i, line)| (lines[i].0, line))
+						.collect()
+				})
+				.unwrap_or(lines)
+		}
+	}
+}
+
+/// Takes the boxed lines out of `lines`, unless they're to be included, grouping runs of
+/// them into sidebars if they're to be kept as notes.
+fn split_sidebars(
+	lines: Vec<(usize, PdfLine)>,
+	sidebars: Sidebars,
+) -> (Vec<(usize, PdfLine)>, Vec<Sidebar>) {
+	if sidebars == Sidebars::Include {
+		return (lines, Vec::new());
+	}
+
+	let mut kept = Vec::new();
+	let mut boxes: Vec<(usize, Vec<String>)> = Vec::new();
+	let mut in_box = false;
+	for (page, line) in lines {
+		if !line.boxed {
+			in_box = false;
+			kept.push((page, line));
+			continue;
+		}
+
+		match boxes.last_mut() {
+			Some((last, texts)) if in_box && *last == page => texts.push(line.text),
+			_ => boxes.push((page, vec![line.text])),
+		}
+		in_box = true;
+	}
+
+	let sidebars = if sidebars == Sidebars::Notes {
+		boxes
+			.into_iter()
+			.filter_map(|(page, texts)| {
+				let mut texts = texts.into_iter().filter(|text| !text.trim().is_empty());
+				let title = texts.next()?.trim().to_owned();
+
+				Some(Sidebar {
+					page,
+					title,
+					text: texts.collect::<Vec<_>>().join("\n"),
+				})
+			})
+			.collect()
+	} else {
+		Vec::new()
+	};
+
+	(kept, sidebars)
+}
+
+/// Where each of `lines`, tagged with their page, ends up once they're joined.
+fn locate_lines(lines: &[(usize, PdfLine)]) -> Vec<Location> {
+	let mut locations = Vec::with_capacity(lines.len());
+	let mut start = 0;
+	for (page, line) in lines {
+		let end = start + line.text.len();
+		locations.push(Location {
+			range: start..end,
+			page: *page,
+			bbox: line.bbox,
+			confidence: line.confidence,
+		});
+		start = end + 1;
+	}
+
+	locations
+}
+
+fn overlaps(a: Rect, b: Rect) -> bool {
+	a.x0 < b.x1 && b.x0 < a.x1 && a.y0 < b.y1 && b.y0 < a.y1
+}
+
+/// Where `part` starts in `whole`, if it's a slice of it.
+fn offset_in(whole: &str, part: &str) -> Option<usize> {
+	let start = (part.as_ptr() as usize).checked_sub(whole.as_ptr() as usize)?;
+	(start + part.len() <= whole.len()).then_some(start)
+}
+
+pub(crate) fn apply_op(text: &mut String, offsets: &mut OffsetMap, op: &Op) -> Result<(), String> {
+	match op {
+		Op::Replace { range, replace } => {
+			let range = check_range(text, range)?;
+			offsets.replace(range.clone(), replace.len());
+			text.replace_range(range, replace);
+		}
+		Op::Insert { pos, char } => {
+			check_pos(text, *pos)?;
+			offsets.replace(*pos..*pos, char.len_utf8());
+			text.insert(*pos, *char);
+		}
+		Op::Delete { range } => {
+			let range = check_range(text, range)?;
+			offsets.replace(range.clone(), 0);
+			text.replace_range(range, "");
+		}
+		Op::Move { range, pos } => {
+			let range = check_range(text, range)?;
+			check_pos(text, *pos)?;
+			if range.start < *pos && *pos < range.end {
+				return Err(format!(
+					"position {pos} is inside the moved range {}..{}",
+					range.start, range.end
+				));
+			}
+
+			let moved: String = text.drain(range.clone()).collect();
+			let pos = if *pos >= range.end {
+				*pos - moved.len()
+			} else {
+				*pos
+			};
+			text.insert_str(pos, &moved);
+			offsets.move_range(range, pos);
+		}
+		Op::RegexReplace { regex, replace } => {
+			let mut replaced = String::with_capacity(text.len());
+			let mut last = 0;
+			let mut changes = Vec::new();
+			for captures in regex.captures_iter(text) {
+				let found = captures.get(0).unwrap();
+				replaced.push_str(&text[last..found.start()]);
+				let start = replaced.len();
+				captures.expand(replace, &mut replaced);
+				changes.push((found.range(), replaced.len() - start));
+				last = found.end();
+			}
+			replaced.push_str(&text[last..]);
+
+			// Back to front, so earlier ranges stay where they were.
+			for (range, len) in changes.into_iter().rev() {
+				offsets.replace(range, len);
+			}
+			*text = replaced;
+		}
+	}
+
+	Ok(())
+}
+
+fn check_pos(text: &str, pos: usize) -> Result<(), String> {
+	if pos > text.len() {
+		Err(format!(
+			"position {pos} is past the end of the text (length {})",
+			text.len()
+		))
+	} else if !text.is_char_boundary(pos) {
+		Err(format!("position {pos} is not on a character boundary"))
+	} else {
+		Ok(())
+	}
+}
+
+fn check_range(text: &str, range: &RangeInclusive<usize>) -> Result<Range<usize>, String> {
+	let (start, end) = (*range.start(), *range.end());
+
+	if start > end {
+		return Err(format!("range {start}..={end} is inverted"));
+	}
+	if end >= text.len() {
+		return Err(format!(
+			"range {start}..={end} is past the end of the text (length {})",
+			text.len()
+		));
+	}
+
+	// Widen the inclusive end to the end of the character it points into.
+	let end = (end + 1..=text.len())
+		.find(|i| text.is_char_boundary(*i))
+		.unwrap_or(text.len());
+	check_pos(text, start)?;
+
+	Ok(start..end)
+}
+
 pub fn extract_text(path: impl AsRef<Path>, source_meta: &SourceMeta) -> Result<PdfExtract> {
-	let pages = crate::backend::extract_pages(path)?;
+	extract_text_with_backend(path, source_meta, backend::default())
+}
+
+/// The pages of `document` read the way `source_meta` says to: with the column layouts and
+/// OCR its sections ask for.
+pub fn extract_meta_pages(
+	document: &dyn TextDocument,
+	source_meta: &SourceMeta,
+) -> anyhow::Result<PdfPages> {
+	let mut columns = BTreeMap::new();
+	let mut ocr = BTreeSet::new();
+	for section in &source_meta.sections {
+		if let Some(layout) = &section.columns {
+			for page in section.pages.clone() {
+				columns.entry(page).or_insert_with(|| layout.clone());
+			}
+		}
+		if section.ocr {
+			ocr.extend(section.pages.clone());
+		}
+	}
+
+	document.extract_pages(&columns, &ocr)
+}
+
+pub fn extract_text_with_backend(
+	path: impl AsRef<Path>,
+	source_meta: &SourceMeta,
+	backend: &dyn TextBackend,
+) -> Result<PdfExtract> {
+	let document = backend.open(path.as_ref())?;
+	let pages = extract_meta_pages(document.as_ref(), source_meta)?;
+	let page_labels = if source_meta.page_labels.is_empty() {
+		document.page_labels()
+	} else {
+		source_meta.page_labels.clone()
+	};
+
+	let normalizer = Normalizer::new(&source_meta.normalization);
 	let sections: Result<Vec<_>> = source_meta
 		.sections
 		.par_iter()
-		.map(|section| process_section(&pages, section, false))
+		.map(|meta| {
+			let mut section = process_section_with(&pages, meta, false, &normalizer)?;
+			section.printed_pages = if page_labels.is_empty() {
+				pages
+					.range(meta.pages.clone())
+					.filter_map(|(i, page)| Some((*i, page.number?)))
+					.collect()
+			} else {
+				meta.pages
+					.clone()
+					.filter_map(|page| Some((page, printed_page(&page_labels, page)?)))
+					.collect()
+			};
+			Ok(section)
+		})
 		.collect();
 
 	Ok(PdfExtract {
 		info: source_meta.info.clone(),
 		sections: sections?,
+		hyphenation: source_meta.hyphenation.clone(),
 	})
 }
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, HashMap};
+
+use cofd_schema::DOT_CHAR;
+use once_cell::sync::Lazy;
+use unicode_normalization::{char::canonical_combining_class, UnicodeNormalization};
+
+use crate::backend::PdfLine;
+
+/// What the books need on top of NFKC, which leaves typographic punctuation and invisible
+/// characters alone.
+static TABLE: Lazy<HashMap<char, String>> = Lazy::new(|| {
+	let dot = DOT_CHAR.to_string();
+
+	[
+		// Quotes and primes.
+		('‘', "'"),
+		('’', "'"),
+		('‚', "'"),
+		('‛', "'"),
+		('′', "'"),
+		('“', "\""),
+		('”', "\""),
+		('„', "\""),
+		('‟', "\""),
+		('″', "\""),
+		// Hyphens and en dashes, with the rarer dashes made em dashes.
+		('‐', "-"),
+		('‑', "-"),
+		('–', "-"),
+		('‒', "—"),
+		('―', "—"),
+		// Invisible characters.
+		('\u{ad}', ""),
+		('\u{200b}', ""),
+		('\u{2060}', ""),
+		('\u{feff}', ""),
+		// Dots set in other glyphs, including the bullets of the Symbol and Wingdings fonts.
+		('●', &dot),
+		('⚫', &dot),
+		('⬤', &dot),
+		('∙', &dot),
+		('⦁', &dot),
+		('\u{f0b7}', &dot),
+		('\u{f06c}', &dot),
+	]
+	.into_iter()
+	.map(|(from, to)| (from, to.to_owned()))
+	.collect()
+});
+
+/// Cleans up extracted text before it's parsed: each char is replaced from a table first,
+/// or dropped if it's from a private-use area, and then the line is normalized to NFKC,
+/// which takes apart ligatures like `ﬁ`, turns non-breaking spaces into plain ones and
+/// puts accents set as combining marks onto their letters.
+#[derive(Clone, Debug)]
+pub struct Normalizer {
+	table: HashMap<char, String>,
+}
+
+impl Default for Normalizer {
+	fn default() -> Self {
+		Self {
+			table: TABLE.clone(),
+		}
+	}
+}
+
+impl Normalizer {
+	/// The built-in table extended with a book's own replacements, which win over it. A char
+	/// replaced with itself is kept as it is.
+	#[must_use]
+	pub fn new(replacements: &BTreeMap<char, String>) -> Self {
+		let mut normalizer = Self::default();
+		normalizer
+			.table
+			.extend(replacements.iter().map(|(from, to)| (*from, to.clone())));
+
+		normalizer
+	}
+
+	#[must_use]
+	pub fn normalize_lines(&self, lines: &[PdfLine]) -> Vec<PdfLine> {
+		lines.iter().map(|line| self.normalize_line(line)).collect()
+	}
+
+	#[must_use]
+	pub fn normalize_line(&self, line: &PdfLine) -> PdfLine {
+		if line
+			.text
+			.chars()
+			.all(|char| char.is_ascii() && !self.table.contains_key(&char))
+		{
+			return line.clone();
+		}
+
+		nfkc(&line.map_chars(|char, _| self.replace_char(char)))
+	}
+
+	fn replace_char(&self, char: char) -> String {
+		if let Some(replacement) = self.table.get(&char) {
+			replacement.clone()
+		} else if is_private_use(char) {
+			String::new()
+		} else {
+			char.to_string()
+		}
+	}
+}
+
+/// Normalizes the whole of `line` to NFKC.
+///
+/// The styles are rebuilt a char and the combining marks after it at a time, which is how
+/// NFKC composes. The rare line where it composes further keeps its first style throughout.
+fn nfkc(line: &PdfLine) -> PdfLine {
+	let text: String = line.text.nfkc().collect();
+	if text == line.text {
+		return line.clone();
+	}
+
+	let mut starts: Vec<usize> = line
+		.text
+		.char_indices()
+		.filter(|(pos, char)| *pos == 0 || canonical_combining_class(*char) == 0)
+		.map(|(pos, _)| pos)
+		.collect();
+	starts.push(line.text.len());
+	let clusters: Vec<(usize, String)> = starts
+		.windows(2)
+		.map(|pair| (pair[0], line.text[pair[0]..pair[1]].nfkc().collect()))
+		.collect();
+
+	let mut out = line.empty_like();
+	if clusters
+		.iter()
+		.map(|(_, text)| text.as_str())
+		.collect::<String>()
+		== text
+	{
+		for (pos, text) in &clusters {
+			match line.style_at(*pos) {
+				Some(style) => out.push_str(text, style),
+				None => out.text.push_str(text),
+			}
+		}
+	} else {
+		match line.style_at(0) {
+			Some(style) => out.push_str(&text, style),
+			None => out.text = text,
+		}
+	}
+
+	out
+}
+
+fn is_private_use(char: char) -> bool {
+	matches!(char, '\u{e000}'..='\u{f8ff}' | '\u{f0000}'..='\u{ffffd}' | '\u{100000}'..='\u{10fffd}')
+}
@@ -0,0 +1,116 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// A run of a section's `extract`, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Piece {
+	len: usize,
+	/// Where in `original` the run starts.
+	original: usize,
+	/// Whether the run was copied from `original`, rather than put in by an op at `original`.
+	copied: bool,
+}
+
+/// Maps byte offsets in a section's `extract` back to `original`, through every op.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetMap {
+	pieces: Vec<Piece>,
+	/// The length of `original`, where the end of `extract` maps to.
+	end: usize,
+}
+
+impl OffsetMap {
+	/// The map of a text to itself.
+	#[must_use]
+	pub fn new(len: usize) -> Self {
+		Self {
+			pieces: vec![Piece {
+				len,
+				original: 0,
+				copied: true,
+			}],
+			end: len,
+		}
+	}
+
+	/// The offset in `original` of byte `pos` of `extract`. Text put in by an op maps to
+	/// where it was put in.
+	#[must_use]
+	pub fn to_original(&self, pos: usize) -> usize {
+		let mut start = 0;
+		for piece in &self.pieces {
+			if pos < start + piece.len {
+				return if piece.copied {
+					piece.original + pos - start
+				} else {
+					piece.original
+				};
+			}
+			start += piece.len;
+		}
+
+		self.end
+	}
+
+	/// Follows `extract.replace_range(range, replacement)`, where the replacement is `len`
+	/// bytes long.
+	pub fn replace(&mut self, range: Range<usize>, len: usize) {
+		let original = self.to_original(range.start);
+		let at = self.cut(range).0;
+		if len > 0 {
+			self.pieces.insert(
+				at,
+				Piece {
+					len,
+					original,
+					copied: false,
+				},
+			);
+		}
+	}
+
+	/// Follows cutting the bytes in `range` and pasting them at `pos`, with `pos` measured
+	/// after the cut.
+	pub fn move_range(&mut self, range: Range<usize>, pos: usize) {
+		let (_, moved) = self.cut(range);
+		let at = self.split(pos);
+		self.pieces.splice(at..at, moved);
+	}
+
+	/// Removes the pieces covering `range`, returning where they were and what they were.
+	fn cut(&mut self, range: Range<usize>) -> (usize, Vec<Piece>) {
+		let start = self.split(range.start);
+		let end = self.split(range.end);
+
+		(start, self.pieces.drain(start..end).collect())
+	}
+
+	/// Splits the piece containing `pos`, returning the index of the piece starting there.
+	fn split(&mut self, pos: usize) -> usize {
+		let mut start = 0;
+		for (i, piece) in self.pieces.iter_mut().enumerate() {
+			if pos == start {
+				return i;
+			}
+			if pos < start + piece.len {
+				let head = pos - start;
+				let tail = Piece {
+					len: piece.len - head,
+					original: if piece.copied {
+						piece.original + head
+					} else {
+						piece.original
+					},
+					copied: piece.copied,
+				};
+				piece.len = head;
+				self.pieces.insert(i + 1, tail);
+				return i + 1;
+			}
+			start += piece.len;
+		}
+
+		self.pieces.len()
+	}
+}
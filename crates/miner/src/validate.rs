@@ -0,0 +1,424 @@
+//! Checking metas for mistakes that would otherwise only show up as a panic or as an empty
+//! section.
+
+use std::{collections::BTreeSet, fmt};
+
+use cofd_meta::{Op, SectionMeta, SectionRange, SourceMeta};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	backend::PdfPages,
+	meta::MetaRegistry,
+	parse::Severity,
+	process_section_with,
+	source::{apply_op, Normalizer, OffsetMap},
+};
+
+/// A mistake found in a meta.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Problem {
+	pub severity: Severity,
+	/// The name of the book the meta is for.
+	pub book: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub section: Option<String>,
+	pub reason: String,
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let severity = match self.severity {
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		};
+		write!(f, "{severity}: {}", self.book)?;
+		if let Some(section) = &self.section {
+			write!(f, ": {section}")?;
+		}
+		write!(f, ": {}", self.reason)
+	}
+}
+
+/// Everything wrong with one or more metas.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+	pub problems: Vec<Problem>,
+}
+
+impl ValidationReport {
+	fn push(
+		&mut self,
+		severity: Severity,
+		meta: &SourceMeta,
+		section: Option<&SectionMeta>,
+		reason: String,
+	) {
+		self.problems.push(Problem {
+			severity,
+			book: meta.info.name.clone(),
+			section: section.map(|section| section.name.clone()),
+			reason,
+		});
+	}
+
+	pub fn extend(&mut self, other: Self) {
+		self.problems.extend(other.problems);
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.problems.is_empty()
+	}
+
+	#[must_use]
+	pub fn has_errors(&self) -> bool {
+		self.errors().next().is_some()
+	}
+
+	pub fn errors(&self) -> impl Iterator<Item = &Problem> {
+		self.problems
+			.iter()
+			.filter(|p| p.severity == Severity::Error)
+	}
+
+	pub fn warnings(&self) -> impl Iterator<Item = &Problem> {
+		self.problems
+			.iter()
+			.filter(|p| p.severity == Severity::Warning)
+	}
+}
+
+/// Checks `meta` on its own, and against the pages of its PDF if they're given, as
+/// [`crate::extract_meta_pages`] reads them for it.
+///
+/// Without the pages, only what the meta says about itself is checked: that page, line and op
+/// ranges aren't inverted, that variants move sections that exist, and that sections of the
+/// same kind don't share pages unless one of them is cut down with a `range`. With them, page
+/// ranges must also be within the PDF, line ranges within their section's lines as the
+/// parse puts them together, regexes must match and every op must apply.
+#[must_use]
+pub fn validate(meta: &SourceMeta, pages: Option<&PdfPages>) -> ValidationReport {
+	let mut report = ValidationReport::default();
+	let normalizer = Normalizer::new(&meta.normalization);
+
+	for section in &meta.sections {
+		let pages_in_order = check_ranges(&mut report, meta, section);
+		let ops_well_formed = check_ops(&mut report, meta, section);
+		if let Some(pages) = pages
+			&& pages_in_order
+		{
+			check_text(
+				&mut report,
+				meta,
+				section,
+				pages,
+				&normalizer,
+				ops_well_formed,
+			);
+		}
+	}
+	check_overlaps(&mut report, meta);
+	for variant in &meta.variants {
+		for name in variant.pages.keys() {
+			if !meta.sections.iter().any(|section| section.name == *name) {
+				report.push(
+					Severity::Warning,
+					meta,
+					None,
+					format!(
+						"variant {:?} moves {name:?}, which isn't a section",
+						variant.name
+					),
+				);
+			}
+		}
+	}
+
+	report
+}
+
+/// Checks every meta in `registry` on its own, and that no two of them share a hash or
+/// fingerprint.
+#[must_use]
+pub fn validate_registry(registry: &MetaRegistry) -> ValidationReport {
+	let mut report = validate_unique(registry.iter());
+	for meta in registry.iter() {
+		report.extend(validate(meta, None));
+	}
+
+	report
+}
+
+/// Checks that no two of `metas`, nor two printings of one of them, have the same hash or
+/// fingerprint, which would make one of them unreachable.
+#[must_use]
+pub fn validate_unique<'a>(metas: impl IntoIterator<Item = &'a SourceMeta>) -> ValidationReport {
+	let mut report = ValidationReport::default();
+	let metas: Vec<&SourceMeta> = metas.into_iter().collect();
+
+	for (i, meta) in metas.iter().enumerate() {
+		check_printings(&mut report, meta);
+		for other in &metas[..i] {
+			for key in shared_keys(meta, other) {
+				report.push(
+					Severity::Error,
+					meta,
+					None,
+					format!("{key} is also {}'s", other.info.name),
+				);
+			}
+		}
+	}
+
+	report
+}
+
+/// Checks that no two printings of `meta` have the same hash or fingerprint.
+fn check_printings(report: &mut ValidationReport, meta: &SourceMeta) {
+	let printings: Vec<(String, u64, Option<u64>)> =
+		std::iter::once((String::from("the book"), meta.info.hash, meta.fingerprint))
+			.chain(meta.variants.iter().map(|variant| {
+				(
+					format!("variant {:?}", variant.name),
+					variant.hash,
+					variant.fingerprint,
+				)
+			}))
+			.collect();
+
+	for (i, (name, hash, fingerprint)) in printings.iter().enumerate() {
+		for (other, other_hash, other_fingerprint) in &printings[..i] {
+			if hash == other_hash {
+				report.push(
+					Severity::Error,
+					meta,
+					None,
+					format!("{name} has the same hash {hash:X} as {other}"),
+				);
+			}
+			if let Some(fingerprint) = fingerprint
+				&& Some(*fingerprint) == *other_fingerprint
+			{
+				report.push(
+					Severity::Error,
+					meta,
+					None,
+					format!("{name} has the same fingerprint {fingerprint:X} as {other}"),
+				);
+			}
+		}
+	}
+}
+
+/// The hashes and fingerprints that printings of `meta` and `other` share, like `hash 1F`.
+pub(crate) fn shared_keys(meta: &SourceMeta, other: &SourceMeta) -> Vec<String> {
+	let (hashes, fingerprints) = keys(meta);
+	let (other_hashes, other_fingerprints) = keys(other);
+
+	hashes
+		.intersection(&other_hashes)
+		.map(|hash| format!("hash {hash:X}"))
+		.chain(
+			fingerprints
+				.intersection(&other_fingerprints)
+				.map(|fingerprint| format!("fingerprint {fingerprint:X}")),
+		)
+		.collect()
+}
+
+/// The hashes and fingerprints of the printings of `meta`: its own and its variants'.
+fn keys(meta: &SourceMeta) -> (BTreeSet<u64>, BTreeSet<u64>) {
+	let printings = std::iter::once((meta.info.hash, meta.fingerprint)).chain(
+		meta.variants
+			.iter()
+			.map(|variant| (variant.hash, variant.fingerprint)),
+	);
+
+	let mut hashes = BTreeSet::new();
+	let mut fingerprints = BTreeSet::new();
+	for (hash, fingerprint) in printings {
+		hashes.insert(hash);
+		fingerprints.extend(fingerprint);
+	}
+	(hashes, fingerprints)
+}
+
+/// The range an op works on, if it has one.
+fn op_range(op: &Op) -> Option<(usize, usize)> {
+	match op {
+		Op::Replace { range, .. } | Op::Delete { range } | Op::Move { range, .. } => {
+			Some((*range.start(), *range.end()))
+		}
+		Op::Insert { .. } | Op::RegexReplace { .. } => None,
+	}
+}
+
+/// Checks the page and line ranges of the section, returning whether its pages are in order.
+fn check_ranges(report: &mut ValidationReport, meta: &SourceMeta, section: &SectionMeta) -> bool {
+	let (start, end) = (*section.pages.start(), *section.pages.end());
+	if start > end {
+		report.push(
+			Severity::Error,
+			meta,
+			Some(section),
+			format!("pages {start}..={end} are inverted"),
+		);
+	}
+	if let Some(SectionRange::Range(range)) = &section.range
+		&& range.start > range.end
+	{
+		report.push(
+			Severity::Error,
+			meta,
+			Some(section),
+			format!("lines {}..{} are inverted", range.start, range.end),
+		);
+	}
+
+	start <= end
+}
+
+/// Checks what can be told about the ops without the text they apply to, returning whether
+/// they're all well-formed.
+fn check_ops(report: &mut ValidationReport, meta: &SourceMeta, section: &SectionMeta) -> bool {
+	let mut well_formed = true;
+	for (index, op) in section.ops.iter().enumerate() {
+		if let Some((start, end)) = op_range(op).filter(|(start, end)| start > end) {
+			report.push(
+				Severity::Error,
+				meta,
+				Some(section),
+				format!("op #{index}: range {start}..={end} is inverted"),
+			);
+			well_formed = false;
+		}
+	}
+
+	well_formed
+}
+
+/// Checks the section against the text of its pages, applying its ops if they're
+/// `well_formed`.
+fn check_text(
+	report: &mut ValidationReport,
+	meta: &SourceMeta,
+	section: &SectionMeta,
+	pages: &PdfPages,
+	normalizer: &Normalizer,
+	well_formed: bool,
+) {
+	let page_count = pages.keys().next_back().map_or(0, |last| last + 1);
+	if *section.pages.end() >= page_count {
+		report.push(
+			Severity::Error,
+			meta,
+			Some(section),
+			format!(
+				"pages {}..={} are past the end of the PDF ({page_count} pages)",
+				section.pages.start(),
+				section.pages.end()
+			),
+		);
+		return;
+	}
+
+	// The range is found among the section's lines before emphasis is marked, and before
+	// sidebars are taken out or the ops are applied.
+	let whole = SectionMeta {
+		range: None,
+		emphasis: false,
+		ops: Vec::new(),
+		..section.clone()
+	};
+	let Ok(whole) = process_section_with(pages, &whole, true, normalizer) else {
+		return;
+	};
+	match &section.range {
+		Some(SectionRange::Regex(regex)) => {
+			if !regex.is_match(&whole.original) {
+				report.push(
+					Severity::Error,
+					meta,
+					Some(section),
+					format!(
+						"regex {:?} doesn't match the section's pages",
+						regex.as_str()
+					),
+				);
+			}
+		}
+		Some(SectionRange::Range(range)) => {
+			let lines = whole.locations.len();
+			if range.end > lines {
+				report.push(
+					Severity::Error,
+					meta,
+					Some(section),
+					format!(
+						"lines {}..{} are past the end of the section's {lines} lines",
+						range.start, range.end
+					),
+				);
+			}
+		}
+		None => {}
+	}
+	if !well_formed {
+		return;
+	}
+
+	let mut unchanged = section.clone();
+	unchanged.ops = Vec::new();
+	let Ok(mut text) =
+		process_section_with(pages, &unchanged, false, normalizer).map(|section| section.extract)
+	else {
+		return;
+	};
+	let mut offsets = OffsetMap::new(text.len());
+	for (index, op) in section.ops.iter().enumerate() {
+		if let Op::RegexReplace { regex, .. } = op
+			&& !regex.is_match(&text)
+		{
+			report.push(
+				Severity::Error,
+				meta,
+				Some(section),
+				format!("op #{index}: regex {:?} doesn't match", regex.as_str()),
+			);
+		}
+		// Later ops' offsets are into text this one was meant to change, so they can't be
+		// checked once one fails.
+		if let Err(reason) = apply_op(&mut text, &mut offsets, op) {
+			report.push(
+				Severity::Error,
+				meta,
+				Some(section),
+				format!("op #{index}: {reason}"),
+			);
+			break;
+		}
+	}
+}
+
+/// Warns about sections of the same kind sharing pages, which is only meant to happen when
+/// at least one of them is cut down with a `range`.
+fn check_overlaps(report: &mut ValidationReport, meta: &SourceMeta) {
+	for (i, a) in meta.sections.iter().enumerate() {
+		for b in &meta.sections[i + 1..] {
+			if a.kind == b.kind
+				&& a.range.is_none()
+				&& b.range.is_none()
+				&& a.pages.start() <= b.pages.end()
+				&& b.pages.start() <= a.pages.end()
+			{
+				report.push(
+					Severity::Warning,
+					meta,
+					Some(b),
+					format!("shares pages with {:?}, a section of the same kind", a.name),
+				);
+			}
+		}
+	}
+}
@@ -0,0 +1,1264 @@
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet},
+	path::Path,
+};
+
+use cofd_meta::{
+	Columns, Identity, PageKind, SectionMeta, SectionRange, Sidebars, SourceMeta, Variant,
+};
+use cofd_miner::{
+	backend::{DocumentInfo, PdfLine, PdfPage, Rect, TextBackend, TextDocument},
+	extract_text_with_backend, hash,
+	identify::{anchors_match, identify_with, identity_of, matching_printing, MIN_CONFIDENCE},
+};
+#[cfg(feature = "lopdf")]
+use lopdf::{
+	content::{Content, Operation},
+	dictionary, Document, Object, Stream,
+};
+
+mod common;
+use common::{label, meta_with, section, variant};
+
+/// Serves the same pages for any path. Lines starting with `|` are in a box, and lines
+/// starting with `~` are only in the page's image, so only OCR reads them. Lines starting
+/// with `>` are in the right-hand column. A page with a line starting with `!` can't be read,
+/// and a line starting with `#` is the PDF's title rather than text on the page.
+struct FakeBackend(Vec<Vec<&'static str>>);
+
+impl TextBackend for FakeBackend {
+	fn open(&self, _path: &Path) -> anyhow::Result<Box<dyn TextDocument>> {
+		Ok(Box::new(FakeDocument(self.0.clone())))
+	}
+}
+
+struct FakeDocument(Vec<Vec<&'static str>>);
+
+impl TextDocument for FakeDocument {
+	fn page_count(&self) -> usize {
+		self.0.len()
+	}
+
+	fn extract_page(&self, index: usize, _columns: Option<&Columns>) -> anyhow::Result<PdfPage> {
+		if let Some(line) = self.0[index].iter().find(|line| line.starts_with('!')) {
+			anyhow::bail!("{}", &line[1..]);
+		}
+		Ok(self.page(index, false))
+	}
+
+	fn info(&self) -> DocumentInfo {
+		DocumentInfo {
+			title: self
+				.0
+				.iter()
+				.flatten()
+				.find_map(|text| text.strip_prefix('#'))
+				.map(String::from),
+			producer: None,
+		}
+	}
+
+	fn page_text(&self, index: usize) -> anyhow::Result<Vec<String>> {
+		Ok(self.0[index]
+			.iter()
+			.filter(|text| !text.starts_with(['~', '#']))
+			.map(|text| text.trim_start_matches(['|', '>']).to_owned())
+			.collect())
+	}
+
+	fn ocr_page(
+		&self,
+		index: usize,
+		_columns: Option<&Columns>,
+	) -> Option<anyhow::Result<PdfPage>> {
+		Some(Ok(self.page(index, true)))
+	}
+}
+
+impl FakeDocument {
+	fn page(&self, index: usize, ocr: bool) -> PdfPage {
+		// One line every 14pt down the page, starting an inch in.
+		let lines = self.0[index]
+			.iter()
+			.filter(|text| !text.starts_with('#') && (ocr || !text.starts_with('~')))
+			.zip(0u8..)
+			.map(|(text, i)| {
+				let x = if text.starts_with('>') { 320.0 } else { 72.0 };
+				PdfLine {
+					bbox: Rect {
+						x0: x,
+						y0: 72.0 + f32::from(i) * 14.0,
+						x1: x + 228.0,
+						y1: 84.0 + f32::from(i) * 14.0,
+					},
+					boxed: text.starts_with('|'),
+					confidence: ocr.then_some(0.75),
+					..PdfLine::from(text.trim_start_matches(['|', '~', '>']))
+				}
+			})
+			.collect();
+
+		PdfPage {
+			width: 612.0,
+			height: 792.0,
+			lines,
+			..PdfPage::default()
+		}
+	}
+}
+
+#[test]
+fn fake_backend() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Front matter"],
+		vec!["Merits", "Giant (•••)"],
+		vec!["\tThe character is huge."],
+	]);
+	let meta = meta_with(vec![section("Merits", 1..=2, PageKind::Merit(None))]);
+
+	let extract = extract_text_with_backend("fake.pdf", &meta, &backend)?;
+	assert_eq!(
+		extract.sections[0].extract,
+		"Merits\nGiant (•••)\n\tThe character is huge."
+	);
+
+	let location = extract.sections[0]
+		.locate(extract.sections[0].original.find("huge").unwrap())
+		.unwrap();
+	assert_eq!(location.page, 2);
+	assert_eq!(location.bbox.y0, 72.0);
+	assert_eq!(extract.sections[0].locate(7).unwrap().bbox.y0, 86.0);
+
+	Ok(())
+}
+
+#[test]
+fn unreadable_page() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Merits", "Giant (•••)"],
+		vec!["!Bad content stream"],
+		vec!["\tThe character is huge."],
+	]);
+	let meta = meta_with(vec![section("Merits", 0..=2, PageKind::Merit(None))]);
+
+	let extract = extract_text_with_backend("fake.pdf", &meta, &backend)?;
+	assert_eq!(
+		extract.sections[0].extract,
+		"Merits\nGiant (•••)\n\tThe character is huge."
+	);
+	assert_eq!(
+		extract.sections[0].unreadable,
+		BTreeMap::from([(1, String::from("Bad content stream"))])
+	);
+
+	let parsed = extract.parse();
+	let errors: Vec<_> = parsed.report.errors().collect();
+	assert_eq!(errors.len(), 1);
+	assert_eq!(errors[0].page, Some(1));
+	assert!(errors[0].reason.contains("Bad content stream"));
+
+	Ok(())
+}
+
+#[test]
+fn page_numbers() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Cover"],
+		vec!["12", "Merits", "Giant (•••)"],
+		vec!["13", "\tThe character is huge."],
+		vec!["14", "Tiny (•)"],
+		vec!["\tThe character is small."],
+	]);
+	let meta = meta_with(vec![section("Merits", 1..=4, PageKind::Merit(None))]);
+
+	let extract = extract_text_with_backend("fake.pdf", &meta, &backend)?;
+	let section = &extract.sections[0];
+	assert_eq!(
+		section.printed_pages,
+		BTreeMap::from([(1, 12), (2, 13), (3, 14)])
+	);
+	assert_eq!(section.printed_page(4), None);
+
+	Ok(())
+}
+
+#[test]
+fn normalization() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![vec![
+		"Giant (\u{f06c}\u{f06c}\u{f06c})",
+		"\tThe ﬁrst “giant” \u{2015} huge\u{a0}and tall\u{ad}est\u{e001}\u{e002}",
+		"\tCru\u{301}ac, with its accent set as a combining mark.",
+	]]);
+	let mut meta = meta_with(vec![section("Merits", 0..=0, PageKind::Merit(None))]);
+	meta.normalization = BTreeMap::from([('\u{e002}', String::from("."))]);
+
+	let extract = extract_text_with_backend("fake.pdf", &meta, &backend)?;
+	assert_eq!(
+		extract.sections[0].extract,
+		"Giant (•••)\n\tThe first \"giant\" — huge and tallest.\n\tCr\u{fa}ac, with its accent set as a combining mark."
+	);
+
+	Ok(())
+}
+
+#[test]
+fn ocr() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Cover"],
+		vec!["Merits", "~Giant (•••)", "~\tThe character is huge."],
+	]);
+	let meta = |ocr| {
+		meta_with(vec![SectionMeta {
+			ocr,
+			..section("Merits", 1..=1, PageKind::Merit(None))
+		}])
+	};
+
+	let extract = extract_text_with_backend("fake.pdf", &meta(false), &backend)?;
+	assert_eq!(extract.sections[0].extract, "Merits");
+	assert!(extract.sections[0]
+		.locations
+		.iter()
+		.all(|loc| loc.confidence.is_none()));
+
+	let extract = extract_text_with_backend("fake.pdf", &meta(true), &backend)?;
+	assert_eq!(
+		extract.sections[0].extract,
+		"Merits\nGiant (•••)\n\tThe character is huge."
+	);
+	assert_eq!(
+		extract.sections[0].locate(7).unwrap().confidence,
+		Some(0.75)
+	);
+
+	let parsed = extract.parse();
+	assert_eq!(parsed.book.merits.len(), 1);
+	let warning = parsed
+		.report
+		.warnings()
+		.find(|warning| warning.reason.contains("OCR"))
+		.unwrap();
+	assert_eq!(
+		warning.reason,
+		"3 lines read with OCR, with a mean confidence of 75%"
+	);
+	assert_eq!(warning.page, Some(1));
+
+	Ok(())
+}
+
+#[test]
+fn ocr_running_text() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Cover"],
+		vec!["~12 Chapter One", "~Giant (•••)"],
+		vec!["~13 Chapter One", "~\tThe character is huge."],
+		vec!["~14 Chapter One", "~Tiny (•)"],
+	]);
+	let meta = meta_with(vec![SectionMeta {
+		ocr: true,
+		..section("Merits", 1..=3, PageKind::Merit(None))
+	}]);
+
+	// The headers only OCR reads are dropped and numbered from like any others.
+	let extract = extract_text_with_backend("fake.pdf", &meta, &backend)?;
+	assert_eq!(
+		extract.sections[0].extract,
+		"Giant (•••)\n\tThe character is huge.\nTiny (•)"
+	);
+	assert_eq!(
+		extract.sections[0].printed_pages,
+		BTreeMap::from([(1, 12), (2, 13), (3, 14)])
+	);
+
+	Ok(())
+}
+
+#[test]
+fn fingerprint() -> anyhow::Result<()> {
+	const TEXT: [&str; 11] = [
+		"\tThe demon calls down fire.",
+		"\tThe demon sends a message.",
+		"\tThe demon remembers every word.",
+		"\tThe demon tracks by scent.",
+		"\tThe demon shrugs off blows.",
+		"\tThe demon speaks with animals.",
+		"\tThe demon knows where to strike.",
+		"\tThe demon changes a corpse.",
+		"\tThe demon reaches out to a ghost.",
+		"\tThe demon calls a dead animal back.",
+		"\tThe demon pushes on.",
+	];
+	let book = |cover: &'static str, watermark: &'static str| {
+		let mut pages = vec![vec![cover, watermark]];
+		pages.extend(TEXT.map(|text| vec!["Chapter One", text, watermark]));
+		pages
+	};
+	let fingerprint = |pages: Vec<Vec<&'static str>>| -> anyhow::Result<u64> {
+		hash::fingerprint_document(FakeBackend(pages).open(Path::new("fake.pdf"))?.as_ref())
+	};
+
+	let jane = fingerprint(book("Cover", "Jane Doe (Order #1234)"))?;
+	let john = fingerprint(book(
+		"Cover, downloaded by John Roe",
+		"John Roe (Order #5678)",
+	))?;
+	let unstamped = fingerprint(book("Cover", ""))?;
+	let mut reprint = book("Cover", "Jane Doe (Order #1234)");
+	reprint[1][1] = "\tThe demon calls down hellfire.";
+	// Only read with OCR, so not part of the raw text.
+	let mut scanned = book("Cover", "");
+	scanned[2].push("~The demon sends a message.");
+	let mut ligatures = book("Cover", "");
+	ligatures[2][1] = "\tThe demon sends a message \u{FB01}rst.";
+	let mut spelled_out = book("Cover", "");
+	spelled_out[2][1] = "\tThe demon sends a message first.";
+
+	assert_eq!(jane, john);
+	assert_eq!(jane, unstamped);
+	assert_eq!(jane, fingerprint(scanned)?);
+	assert_eq!(fingerprint(ligatures)?, fingerprint(spelled_out)?);
+	assert_ne!(jane, fingerprint(reprint)?);
+
+	Ok(())
+}
+
+#[test]
+fn variants() -> anyhow::Result<()> {
+	let mut meta = meta_with(vec![
+		section("Merits", 1..=1, PageKind::Merit(None)),
+		section("Exploits", 2..=2, PageKind::Exploit),
+	]);
+	meta.info.hash = 1;
+	meta.page_labels = vec![label(1, Some(1))];
+	meta.variants = vec![Variant {
+		fingerprint: Some(3),
+		page_offset: 1,
+		pages: BTreeMap::from([(String::from("Exploits"), 5..=5)]),
+		..variant("POD", 2)
+	}];
+	let backend = FakeBackend(vec![
+		vec!["Cover"],
+		vec!["Print-on-demand notice"],
+		vec!["Giant (•••)", "\tThe character is huge."],
+		vec!["Ad"],
+		vec!["Ad"],
+		vec!["Hellfire", "\tThe demon calls down fire."],
+	]);
+
+	assert!(matches!(meta.for_hash(1), Some(Cow::Borrowed(_))));
+	assert!(meta.for_hash(4).is_none());
+
+	let pod = meta.for_fingerprint(3).unwrap();
+	assert_eq!(pod.info.hash, 2);
+	assert!(pod.variants.is_empty());
+	assert_eq!(pod.page_labels[0].start, 2);
+	assert_eq!(pod.sections[0].pages, 2..=2);
+	assert_eq!(pod.sections[1].pages, 5..=5);
+
+	let extract = extract_text_with_backend("fake.pdf", &pod, &backend)?;
+	assert_eq!(
+		extract.sections[0].extract,
+		"Giant (•••)\n\tThe character is huge."
+	);
+	assert_eq!(
+		extract.sections[1].extract,
+		"Hellfire\n\tThe demon calls down fire."
+	);
+	assert_eq!(extract.sections[0].printed_page(2), Some(1));
+
+	Ok(())
+}
+
+#[test]
+fn identify() -> anyhow::Result<()> {
+	let book = |watermark: &'static str| {
+		FakeBackend(vec![
+			vec!["Demon the Descent", watermark],
+			vec!["Credits", "Written by a demon", watermark],
+			vec!["Contents", watermark],
+			vec!["Embeds", "\tThe demon sends a message.", watermark],
+			vec![
+				"Exploits",
+				"Hellfire",
+				"\tThe demon calls down fire.",
+				watermark,
+			],
+		])
+	};
+	let meta = |name: &str, identity, anchor| SourceMeta {
+		identity,
+		sections: vec![SectionMeta {
+			range: Some(SectionRange::Regex(regex::Regex::new(anchor).unwrap())),
+			..section("Exploits", 4..=4, PageKind::Exploit)
+		}],
+		..common::meta(0, name)
+	};
+
+	let identity = identity_of(
+		book("Jane Doe (Order #1234)")
+			.open(Path::new("fake.pdf"))?
+			.as_ref(),
+	)?;
+	let metas = [
+		meta(
+			"Mage the Awakening",
+			Identity {
+				page_count: Some(400),
+				..Identity::default()
+			},
+			"Hellfire",
+		),
+		meta("Demon the Descent", identity, "Hellfire"),
+	];
+
+	let backend = book("John Roe (Order #5678)");
+	let candidates = identify_with("fake.pdf", &metas, &backend)?;
+	assert_eq!(candidates.len(), 1);
+	assert_eq!(candidates[0].meta.info.name, "Demon the Descent");
+	assert!(candidates[0].confidence >= MIN_CONFIDENCE);
+
+	assert!(anchors_match("fake.pdf", candidates[0].meta, &backend)?);
+	let moved = meta("Demon the Descent", Identity::default(), "Soul Marks");
+	assert!(!anchors_match("fake.pdf", &moved, &backend)?);
+
+	// A printing with the exploits a page earlier finds its anchor once shifted.
+	let mut reprint = meta("Demon the Descent", Identity::default(), "Hellfire");
+	reprint.sections[0].pages = 3..=3;
+	assert!(!anchors_match("fake.pdf", &reprint, &backend)?);
+	reprint.variants.push(Variant {
+		page_offset: 1,
+		..variant("POD", 1)
+	});
+	let printing = matching_printing("fake.pdf", &reprint, &backend)?.unwrap();
+	assert_eq!(printing.info.hash, 1);
+
+	Ok(())
+}
+
+#[test]
+fn identify_by_title() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["#Demon: The Descent", "Demon the Descent"],
+		vec!["Credits", "Written by a demon"],
+	]);
+	let metas = [
+		common::meta(1, "Mage the Awakening"),
+		common::meta(2, "Demon the Descent"),
+	];
+
+	// Neither meta gives an identity, so the title is all there is to go on.
+	let candidates = identify_with("fake.pdf", &metas, &backend)?;
+	assert_eq!(candidates.len(), 2);
+	assert_eq!(candidates[0].meta.info.name, "Demon the Descent");
+	assert!(candidates[0].confidence >= MIN_CONFIDENCE);
+	assert!(candidates[1].confidence < MIN_CONFIDENCE);
+
+	Ok(())
+}
+
+#[test]
+fn running_headers() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Front matter", "Effect:"],
+		vec!["112 Chapter Three: Laws of the Kill", "Effect:"],
+		vec!["113 Chapter Three: Laws of the Kill", "Effect:"],
+		vec!["114 Chapter Three: Laws of the Kill", "Effect:"],
+	]);
+
+	// The repeated second lines are too far down their pages to be headers.
+	let pages = backend
+		.open(Path::new("fake.pdf"))?
+		.extract_pages(&BTreeMap::new(), &BTreeSet::new())?;
+	let text: Vec<Vec<&str>> = pages
+		.values()
+		.map(|page| page.lines.iter().map(|line| line.text.as_str()).collect())
+		.collect();
+	assert_eq!(
+		text,
+		[
+			vec!["Front matter", "Effect:"],
+			vec!["Effect:"],
+			vec!["Effect:"],
+			vec!["Effect:"],
+		]
+	);
+
+	Ok(())
+}
+
+#[test]
+fn repeated_body_lines() -> anyhow::Result<()> {
+	const LONG: &str = "The character is huge, and gains a point of Size for it, and Health.";
+	let backend = FakeBackend(vec![
+		vec!["Merits", "Giant (•••)"],
+		vec![">Merits", "Tiny (•)"],
+		vec!["Merits", "Strong (••)"],
+		vec![">Merits", "Weak (•)"],
+		vec![LONG],
+		vec![LONG],
+		vec![LONG],
+	]);
+
+	// The same heading at the top of pages, but not at the same place on three of them,
+	// and a line too long to be a header, are both kept.
+	let pages = backend
+		.open(Path::new("fake.pdf"))?
+		.extract_pages(&BTreeMap::new(), &BTreeSet::new())?;
+	let first: Vec<&str> = pages
+		.values()
+		.map(|page| page.lines[0].text.as_str())
+		.collect();
+	assert_eq!(
+		first,
+		["Merits", "Merits", "Merits", "Merits", LONG, LONG, LONG]
+	);
+
+	Ok(())
+}
+
+#[test]
+fn page_labels() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Cover"],
+		vec!["Hellfire", "Cost: 1 Aether", "\tThe demon calls down fire."],
+		vec!["Smite", "Action: Instant", "\tThe demon strikes."],
+	]);
+	let mut meta = meta_with(vec![section("Exploits", 1..=2, PageKind::Exploit)]);
+	meta.page_labels = vec![label(0, None), label(2, Some(17))];
+
+	let parsed = extract_text_with_backend("fake.pdf", &meta, &backend)?.parse();
+	let exploits = &parsed.exploits;
+	assert_eq!(exploits[0].name, "Hellfire");
+	assert_eq!(exploits[1].name, "Smite");
+	assert_eq!(exploits[1].reference.1, 17);
+
+	let diagnostics: Vec<_> = parsed.report.warnings().collect();
+	assert_eq!(diagnostics.len(), 1);
+	assert_eq!(diagnostics[0].page, Some(1));
+	assert_eq!(
+		diagnostics[0].reason,
+		"PDF page 1 has no printed page number"
+	);
+
+	Ok(())
+}
+
+#[test]
+fn sidebars() -> anyhow::Result<()> {
+	let backend = FakeBackend(vec![
+		vec!["Cover"],
+		vec![
+			"Hellfire",
+			"Cost: 1 Aether",
+			"|Playing With Fire",
+			"|\tBurns from Hellfire heal slowly.",
+			"\tThe demon calls down fire.",
+		],
+	]);
+	let meta = |sidebars| SourceMeta {
+		page_labels: vec![label(1, Some(12))],
+		..meta_with(vec![SectionMeta {
+			sidebars,
+			..section("Exploits", 1..=1, PageKind::Exploit)
+		}])
+	};
+
+	let included = extract_text_with_backend("fake.pdf", &meta(Sidebars::Include), &backend)?;
+	assert!(included.sections[0].extract.contains("Playing With Fire"));
+
+	let excluded = extract_text_with_backend("fake.pdf", &meta(Sidebars::Exclude), &backend)?;
+	assert_eq!(
+		excluded.sections[0].extract,
+		"Hellfire\nCost: 1 Aether\n\tThe demon calls down fire."
+	);
+	assert!(excluded.sections[0].sidebars.is_empty());
+
+	let parsed = extract_text_with_backend("fake.pdf", &meta(Sidebars::Notes), &backend)?.parse();
+	assert_eq!(parsed.exploits.len(), 1);
+	assert_eq!(parsed.exploits[0].name, "Hellfire");
+	assert_eq!(parsed.notes.len(), 1);
+	assert_eq!(parsed.notes[0].name, "Playing With Fire");
+	assert_eq!(
+		parsed.notes[0].description,
+		["Burns from Hellfire heal slowly."]
+	);
+	assert_eq!(parsed.notes[0].reference.1, 12);
+	assert_eq!(parsed.notes[0].inner.section, "Exploits");
+
+	Ok(())
+}
+
+/// Writes a one-page PDF showing each `(x, y, font, text)` at 12pt, where `font` is `F1`
+/// (Helvetica), `F2` (Helvetica-Oblique) or `F3` (Helvetica-Bold).
+#[cfg(feature = "lopdf")]
+fn write_pdf(path: &Path, lines: &[(i64, i64, &str, Object)]) -> anyhow::Result<()> {
+	write_page(path, text_operations(lines), Vec::new())
+}
+
+/// The operations that show each `(x, y, font, text)`, as in [`write_pdf`].
+#[cfg(feature = "lopdf")]
+fn text_operations(lines: &[(i64, i64, &str, Object)]) -> Vec<Operation> {
+	let mut operations = Vec::new();
+	for (x, y, font, text) in lines {
+		let show = if matches!(text, Object::Array(_)) {
+			"TJ"
+		} else {
+			"Tj"
+		};
+		operations.extend([
+			Operation::new("BT", vec![]),
+			Operation::new(
+				"Tf",
+				vec![Object::Name(font.as_bytes().to_vec()), 12.into()],
+			),
+			Operation::new("Td", vec![(*x).into(), (*y).into()]),
+			Operation::new(show, vec![text.clone()]),
+			Operation::new("ET", vec![]),
+		]);
+	}
+	operations
+}
+
+/// Writes a one-page PDF drawing `operations`, with the fonts of [`write_pdf`] and
+/// `(name, matrix, operations)` Form XObjects that use the page's resources.
+#[cfg(feature = "lopdf")]
+fn write_page(
+	path: &Path,
+	operations: Vec<Operation>,
+	forms: Vec<(&str, [i64; 6], Vec<Operation>)>,
+) -> anyhow::Result<()> {
+	write_pages(path, vec![operations], forms)
+}
+
+/// Like [`write_page`], with a page drawing each of `pages`.
+#[cfg(feature = "lopdf")]
+fn write_pages(
+	path: &Path,
+	pages: Vec<Vec<Operation>>,
+	forms: Vec<(&str, [i64; 6], Vec<Operation>)>,
+) -> anyhow::Result<()> {
+	let mut doc = Document::with_version("1.5");
+	let pages_id = doc.new_object_id();
+
+	let mut fonts = lopdf::Dictionary::new();
+	for (name, base_font) in [
+		("F1", "Helvetica"),
+		("F2", "Helvetica-Oblique"),
+		("F3", "Helvetica-Bold"),
+	] {
+		let font_id = doc.add_object(dictionary! {
+			"Type" => "Font",
+			"Subtype" => "Type1",
+			"BaseFont" => base_font,
+		});
+		fonts.set(name, font_id);
+	}
+	let mut xobjects = lopdf::Dictionary::new();
+	for (name, matrix, operations) in forms {
+		let form = Stream::new(
+			dictionary! {
+				"Type" => "XObject",
+				"Subtype" => "Form",
+				"BBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+				"Matrix" => matrix.map(Object::from).to_vec(),
+			},
+			Content { operations }.encode()?,
+		);
+		xobjects.set(name, doc.add_object(form));
+	}
+	let resources_id = doc.add_object(dictionary! { "Font" => fonts, "XObject" => xobjects });
+
+	let mut kids = Vec::new();
+	for operations in pages {
+		let content = Content { operations };
+		let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode()?));
+		let page_id = doc.add_object(dictionary! {
+			"Type" => "Page",
+			"Parent" => pages_id,
+			"Contents" => content_id,
+		});
+		kids.push(Object::from(page_id));
+	}
+	let count = i64::try_from(kids.len())?;
+	doc.objects.insert(
+		pages_id,
+		Object::Dictionary(dictionary! {
+			"Type" => "Pages",
+			"Kids" => kids,
+			"Count" => count,
+			"Resources" => resources_id,
+			"MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+		}),
+	);
+	let catalog_id = doc.add_object(dictionary! {
+		"Type" => "Catalog",
+		"Pages" => pages_id,
+	});
+	doc.trailer.set("Root", catalog_id);
+
+	doc.save(path)?;
+	Ok(())
+}
+
+/// Writes a one-page PDF that's nothing but a `width` by `height` grayscale image.
+#[cfg(all(feature = "ocr", feature = "lopdf"))]
+fn write_image_page(path: &Path, width: u32, height: u32, samples: Vec<u8>) -> anyhow::Result<()> {
+	let mut doc = Document::with_version("1.5");
+	let pages_id = doc.new_object_id();
+
+	let image_id = doc.add_object(Stream::new(
+		dictionary! {
+			"Type" => "XObject",
+			"Subtype" => "Image",
+			"Width" => i64::from(width),
+			"Height" => i64::from(height),
+			"ColorSpace" => "DeviceGray",
+			"BitsPerComponent" => 8,
+		},
+		samples,
+	));
+	let resources_id = doc.add_object(dictionary! {
+		"XObject" => dictionary! { "Im1" => image_id },
+	});
+	let content = Content {
+		operations: vec![
+			Operation::new("q", vec![]),
+			Operation::new(
+				"cm",
+				vec![
+					612.into(),
+					0.into(),
+					0.into(),
+					792.into(),
+					0.into(),
+					0.into(),
+				],
+			),
+			Operation::new("Do", vec!["Im1".into()]),
+			Operation::new("Q", vec![]),
+		],
+	};
+	let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode()?));
+
+	let page_id = doc.add_object(dictionary! {
+		"Type" => "Page",
+		"Parent" => pages_id,
+		"Contents" => content_id,
+	});
+	doc.objects.insert(
+		pages_id,
+		Object::Dictionary(dictionary! {
+			"Type" => "Pages",
+			"Kids" => vec![page_id.into()],
+			"Count" => 1,
+			"Resources" => resources_id,
+			"MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+		}),
+	);
+	let catalog_id = doc.add_object(dictionary! {
+		"Type" => "Catalog",
+		"Pages" => pages_id,
+	});
+	doc.trailer.set("Root", catalog_id);
+
+	doc.save(path)?;
+	Ok(())
+}
+
+/// Reads a scan with the real Tesseract: a page of text rendered to an image and saved as
+/// a page with no text layer.
+#[test]
+#[cfg(all(feature = "ocr", feature = "lopdf"))]
+fn ocr_image_page() -> anyhow::Result<()> {
+	use cofd_miner::backend::mupdf::MuPdf;
+	use mupdf::{Colorspace, Matrix};
+
+	let dir = std::env::temp_dir();
+	let text_path = dir.join(format!("cofd-miner-ocr-text-{}.pdf", std::process::id()));
+	let image_path = dir.join(format!("cofd-miner-ocr-image-{}.pdf", std::process::id()));
+	write_pdf(
+		&text_path,
+		&[
+			(72, 720, "F3", Object::string_literal("Giant")),
+			(
+				72,
+				700,
+				"F1",
+				Object::string_literal("The character is huge."),
+			),
+		],
+	)?;
+
+	let pixmap = mupdf::Document::open(text_path.to_str().unwrap())?
+		.load_page(0)?
+		.to_pixmap(
+			&Matrix::new_scale(4.0, 4.0),
+			&Colorspace::device_gray(),
+			0.0,
+			false,
+		)?;
+	let width = usize::try_from(pixmap.width())?;
+	let samples = pixmap
+		.samples()
+		.chunks(usize::try_from(pixmap.stride())?)
+		.flat_map(|row| &row[..width])
+		.copied()
+		.collect();
+	write_image_page(&image_path, pixmap.width(), pixmap.height(), samples)?;
+
+	let extract = extract_text_with_backend(
+		&image_path,
+		&meta_with(vec![section("Test", 0..=0, PageKind::default())]),
+		&MuPdf,
+	);
+	std::fs::remove_file(&text_path)?;
+	std::fs::remove_file(&image_path)?;
+
+	let section = &extract?.sections[0];
+	assert_eq!(section.extract, "Giant\nThe character is huge.");
+	assert!(section.locations.iter().all(|loc| loc.confidence.is_some()));
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_layout() -> anyhow::Result<()> {
+	let path = std::env::temp_dir().join(format!("cofd-miner-lopdf-{}.pdf", std::process::id()));
+	write_pdf(
+		&path,
+		&[
+			(72, 720, "F1", Object::string_literal("Merits")),
+			(
+				90,
+				706,
+				"F1",
+				Object::string_literal("The first paragraph."),
+			),
+			(72, 692, "F1", Object::string_literal("It continues here.")),
+			(
+				320,
+				720,
+				"F1",
+				Object::Array(vec![
+					Object::string_literal("Right"),
+					(-250).into(),
+					Object::string_literal("column"),
+				]),
+			),
+			(320, 706, "F1", Object::string_literal("continues.")),
+			(300, 40, "F1", Object::string_literal("42")),
+		],
+	)?;
+
+	let pages = cofd_miner::backend::lopdf::LoPdf
+		.open(&path)
+		.and_then(|doc| doc.extract_pages(&BTreeMap::new(), &BTreeSet::new()));
+	std::fs::remove_file(&path)?;
+
+	// A page number on a page of its own can't be told from any other number, so it's kept.
+	assert_eq!(
+		pages?[&0]
+			.lines
+			.iter()
+			.map(|line| line.text.as_str())
+			.collect::<Vec<_>>(),
+		[
+			"Merits",
+			"\tThe first paragraph.",
+			"It continues here.",
+			"Right column",
+			"continues.",
+			"42",
+		]
+	);
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_forms_and_invisible_text() -> anyhow::Result<()> {
+	let path = std::env::temp_dir().join(format!("cofd-miner-forms-{}.pdf", std::process::id()));
+	let mut operations = text_operations(&[(72, 720, "F1", Object::string_literal("Merits"))]);
+	operations.push(Operation::new("Do", vec![Object::Name(b"Fm1".to_vec())]));
+	operations.extend([
+		Operation::new("BT", vec![]),
+		Operation::new("Tr", vec![3.into()]),
+		Operation::new("Tf", vec![Object::Name(b"F1".to_vec()), 12.into()]),
+		Operation::new("Td", vec![72.into(), 692.into()]),
+		Operation::new(
+			"Tj",
+			vec![Object::string_literal("Only in the text layer.")],
+		),
+		Operation::new("ET", vec![]),
+	]);
+	write_page(
+		&path,
+		operations,
+		vec![(
+			"Fm1",
+			[1, 0, 0, 1, 0, -14],
+			text_operations(&[(72, 720, "F1", Object::string_literal("Drawn by a form."))]),
+		)],
+	)?;
+
+	let pages = cofd_miner::backend::lopdf::LoPdf
+		.open(&path)
+		.and_then(|doc| doc.extract_pages(&BTreeMap::new(), &BTreeSet::new()));
+	std::fs::remove_file(&path)?;
+
+	assert_eq!(
+		pages?[&0]
+			.lines
+			.iter()
+			.map(|line| line.text.as_str())
+			.collect::<Vec<_>>(),
+		["Merits", "Drawn by a form.", "Only in the text layer."]
+	);
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_page_numbers() -> anyhow::Result<()> {
+	use cofd_miner::backend::lopdf::LoPdf;
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-folios-{}.pdf", std::process::id()));
+	let pages = [
+		("Giant (•••)", "12"),
+		("\tThe character is huge.", "13"),
+		("Tiny (•)", "14"),
+	]
+	.map(|(text, number)| {
+		text_operations(&[
+			(72, 720, "F1", Object::string_literal(text)),
+			(300, 40, "F1", Object::string_literal(number)),
+		])
+	});
+	write_pages(&path, pages.to_vec(), Vec::new())?;
+
+	let meta = meta_with(vec![section("Merits", 0..=2, PageKind::Merit(None))]);
+	let extract = extract_text_with_backend(&path, &meta, &LoPdf);
+	std::fs::remove_file(&path)?;
+
+	// The page numbers are on lines of their own, well below the text.
+	let section = &extract?.sections[0];
+	assert_eq!(
+		section.printed_pages,
+		BTreeMap::from([(0, 12), (1, 13), (2, 14)])
+	);
+	assert!(!section.extract.contains("12"));
+
+	Ok(())
+}
+
+/// A narrow page whose second column starts left of where a fixed split would put it.
+#[cfg(feature = "lopdf")]
+const NARROW_COLUMNS: [(i64, i64, &str); 7] = [
+	(50, 740, "Heading"),
+	(50, 720, "Left one"),
+	(50, 706, "Left two"),
+	(62, 692, "Left three"),
+	(200, 720, "Right one"),
+	(200, 706, "Right two"),
+	(150, 40, "7"),
+];
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_columns() -> anyhow::Result<()> {
+	use cofd_miner::backend::lopdf::LoPdf;
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-columns-{}.pdf", std::process::id()));
+	write_pdf(
+		&path,
+		&NARROW_COLUMNS.map(|(x, y, text)| (x, y, "F1", Object::string_literal(text))),
+	)?;
+
+	let meta = |columns| {
+		meta_with(vec![SectionMeta {
+			columns,
+			..section("Test", 0..=0, PageKind::default())
+		}])
+	};
+	let detected = extract_text_with_backend(&path, &meta(None), &LoPdf);
+	let single = extract_text_with_backend(&path, &meta(Some(Columns::Count(1))), &LoPdf);
+	std::fs::remove_file(&path)?;
+
+	assert_eq!(
+		detected?.sections[0].extract,
+		"Heading\nLeft one\nLeft two\n\tLeft three\nRight one\nRight two\n7"
+	);
+	assert_eq!(
+		single?.sections[0].extract,
+		"Heading\nLeft one\nLeft two\n\tLeft three\n\tRight one\n\tRight two\n7"
+	);
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_styles() -> anyhow::Result<()> {
+	use cofd_miner::backend::lopdf::LoPdf;
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-styles-{}.pdf", std::process::id()));
+	write_pdf(
+		&path,
+		&[
+			(72, 720, "F3", Object::string_literal("Giant")),
+			(72, 706, "F1", Object::string_literal("The character is ")),
+			(174, 706, "F2", Object::string_literal("huge.")),
+		],
+	)?;
+
+	let extract = extract_text_with_backend(
+		&path,
+		&meta_with(vec![SectionMeta {
+			emphasis: true,
+			..section("Test", 0..=0, PageKind::default())
+		}]),
+		&LoPdf,
+	);
+	std::fs::remove_file(&path)?;
+
+	let section = &extract?.sections[0];
+	assert_eq!(section.extract, "Giant\nThe character is *huge.*");
+	assert!(section
+		.style_at(0)
+		.is_some_and(|style| style.bold && style.size == 12.0));
+	assert!(section
+		.style_at(6)
+		.is_some_and(|style| !style.bold && !style.italic));
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_tables() -> anyhow::Result<()> {
+	use cofd_meta::TableKind;
+	use cofd_miner::backend::lopdf::LoPdf;
+
+	const COLUMNS: [i64; 7] = [72, 150, 200, 280, 345, 385, 475];
+	let rows: [(i64, &[&str]); 5] = [
+		(
+			706,
+			&[
+				"Type",
+				"Damage",
+				"Initiative",
+				"Strength",
+				"Size",
+				"Availability",
+				"Special",
+			],
+		),
+		(692, &["Sword", "2", "-3", "2", "2", "2", "—"]),
+		(678, &["Brass", "0", "0", "1", "1", "1", "Grapple"]),
+		(664, &["knuckles"]),
+		(650, &["Spear", "2", "-2", "2", "4", "1", "+1 Defense"]),
+	];
+	let mut cells = vec![(72, 730, "F3", Object::string_literal("Melee Weapons"))];
+	for (y, row) in rows {
+		cells.extend(
+			COLUMNS
+				.iter()
+				.zip(row)
+				.map(|(x, text)| (*x, y, "F1", Object::string_literal(*text))),
+		);
+	}
+	// Both in the first column, so the table ends here.
+	cells.push((72, 636, "F1", Object::string_literal("Axe")));
+	cells.push((110, 636, "F1", Object::string_literal("3")));
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-tables-{}.pdf", std::process::id()));
+	write_pdf(&path, &cells)?;
+	let mut meta = meta_with(vec![section(
+		"Melee Weapons",
+		0..=0,
+		PageKind::Table(TableKind::Weapon),
+	)]);
+	meta.page_labels = vec![label(0, Some(1))];
+	let extract = extract_text_with_backend(&path, &meta, &LoPdf);
+	std::fs::remove_file(&path)?;
+
+	let extract = extract?;
+	let tables = &extract.sections[0].tables;
+	assert_eq!(tables.len(), 1);
+	assert_eq!(tables[0].table.header[2], "Initiative");
+	assert_eq!(tables[0].table.rows.len(), 3);
+	assert_eq!(tables[0].table.cut_off.as_deref(), Some("Axe 3"));
+
+	let parsed = extract.parse();
+	let warnings: Vec<_> = parsed.report.warnings().collect();
+	assert_eq!(warnings.len(), 1, "{:?}", parsed.report.diagnostics);
+	assert_eq!(warnings[0].snippet, "Axe 3");
+	assert!(!parsed.report.has_errors());
+	let names: Vec<_> = parsed.weapons.iter().map(|w| w.name.as_str()).collect();
+	assert_eq!(names, ["Brass knuckles", "Spear", "Sword"]);
+	assert_eq!(parsed.weapons[0].inner.tags, ["Grapple"]);
+	assert_eq!(parsed.weapons[1].inner.size, 4);
+	assert_eq!(parsed.weapons[2].inner.initiative, -3);
+	assert!(parsed.weapons[2].inner.tags.is_empty());
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_boxes() -> anyhow::Result<()> {
+	use cofd_miner::backend::lopdf::LoPdf;
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-boxes-{}.pdf", std::process::id()));
+	write_pdf(
+		&path,
+		&[
+			(72, 720, "F1", Object::string_literal("Running text")),
+			(90, 680, "F3", Object::string_literal("Sidebar")),
+			(90, 666, "F1", Object::string_literal("Inside the box")),
+			(72, 620, "F1", Object::string_literal("Underlined")),
+			(72, 600, "F1", Object::string_literal("Clipped")),
+		],
+	)?;
+
+	// A shaded box behind the sidebar, a rule under one line and a clipping path around
+	// another, all drawn before the text.
+	let mut doc = Document::load(&path)?;
+	let page_id = doc.page_iter().next().unwrap();
+	let mut content = Content::decode(&doc.get_page_content(page_id)?)?;
+	let rect = |x: i64, y: i64, w: i64, h: i64| {
+		Operation::new("re", vec![x.into(), y.into(), w.into(), h.into()])
+	};
+	content.operations.splice(
+		0..0,
+		[
+			rect(80, 655, 300, 45),
+			Operation::new("f", vec![]),
+			rect(72, 617, 120, 1),
+			Operation::new("f", vec![]),
+			rect(60, 590, 200, 30),
+			Operation::new("n", vec![]),
+		],
+	);
+	doc.change_page_content(page_id, content.encode()?)?;
+	doc.save(&path)?;
+
+	let pages = LoPdf
+		.open(&path)
+		.and_then(|doc| doc.extract_pages(&BTreeMap::new(), &BTreeSet::new()));
+	std::fs::remove_file(&path)?;
+
+	let pages = pages?;
+	let boxed: Vec<_> = pages[&0]
+		.lines
+		.iter()
+		.map(|line| (line.text.trim(), line.boxed))
+		.collect();
+	assert_eq!(
+		boxed,
+		[
+			("Running text", false),
+			("Sidebar", true),
+			("Inside the box", true),
+			("Underlined", false),
+			("Clipped", false),
+		]
+	);
+
+	Ok(())
+}
+
+#[test]
+#[cfg(feature = "lopdf")]
+fn lopdf_page_labels() -> anyhow::Result<()> {
+	use cofd_miner::backend::lopdf::LoPdf;
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-labels-{}.pdf", std::process::id()));
+	write_pdf(&path, &[(72, 720, "F1", Object::string_literal("Text"))])?;
+
+	let mut doc = Document::load(&path)?;
+	let labels = doc.add_object(dictionary! {
+		"Nums" => vec![
+			0.into(),
+			dictionary! { "S" => "r" }.into(),
+			4.into(),
+			dictionary! { "S" => "D", "St" => 3 }.into(),
+			9.into(),
+			dictionary! { "S" => "D", "P" => Object::string_literal("A-") }.into(),
+		],
+	});
+	doc.catalog_mut()?.set("PageLabels", labels);
+	doc.save(&path)?;
+
+	let labels = LoPdf.open(&path).map(|doc| doc.page_labels());
+	std::fs::remove_file(&path)?;
+
+	assert_eq!(labels?, [label(0, None), label(4, Some(3)), label(9, None)]);
+
+	Ok(())
+}
+
+/// Both backends should give the parsers the same text for the same page.
+#[test]
+#[cfg(all(feature = "mupdf", feature = "lopdf"))]
+fn backends_agree() -> anyhow::Result<()> {
+	use cofd_miner::backend::{lopdf::LoPdf, mupdf::MuPdf};
+
+	let path = std::env::temp_dir().join(format!("cofd-miner-agree-{}.pdf", std::process::id()));
+	write_pdf(
+		&path,
+		&[
+			(72, 720, "F3", Object::string_literal("Merits")),
+			(72, 700, "F1", Object::string_literal("Giant (3 dots)")),
+			(
+				90,
+				686,
+				"F1",
+				Object::string_literal("Your character is massive, well over"),
+			),
+			(72, 672, "F1", Object::string_literal("six feet tall.")),
+			(
+				72,
+				652,
+				"F2",
+				Object::string_literal("Drawback: Clothes rarely fit."),
+			),
+			(
+				320,
+				720,
+				"F1",
+				Object::Array(vec![
+					Object::string_literal("Fleet"),
+					(-250).into(),
+					Object::string_literal("of Foot (1 to 3 dots)"),
+				]),
+			),
+			(
+				338,
+				706,
+				"F1",
+				Object::string_literal("Your character is quick."),
+			),
+		],
+	)?;
+	let meta = meta_with(vec![SectionMeta {
+		emphasis: true,
+		..section("Merits", 0..=0, PageKind::Merit(None))
+	}]);
+
+	let mupdf = extract_text_with_backend(&path, &meta, &MuPdf);
+	let lopdf = extract_text_with_backend(&path, &meta, &LoPdf);
+	std::fs::remove_file(&path)?;
+
+	let (mupdf, lopdf) = (mupdf?, lopdf?);
+	for (mupdf, lopdf) in mupdf.sections.iter().zip(&lopdf.sections) {
+		similar_asserts::assert_eq!(mupdf.extract, lopdf.extract, "{}", mupdf.name);
+	}
+
+	Ok(())
+}
@@ -0,0 +1,61 @@
+//! Fixtures shared by the integration tests. Each test crate only uses some of them.
+#![allow(dead_code)]
+
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use cofd_meta::{Identity, PageKind, PageLabel, SectionMeta, Sidebars, SourceMeta, Variant};
+use cofd_schema::book::BookInfo;
+
+/// A meta with nothing but `sections`.
+pub fn meta_with(sections: Vec<SectionMeta>) -> SourceMeta {
+	SourceMeta {
+		info: BookInfo::default(),
+		fingerprint: None,
+		page_labels: Vec::new(),
+		hyphenation: Vec::new(),
+		normalization: BTreeMap::new(),
+		variants: Vec::new(),
+		identity: Identity::default(),
+		sections,
+	}
+}
+
+/// A meta for the book `name` with `hash` and no sections.
+pub fn meta(hash: u64, name: &str) -> SourceMeta {
+	let mut meta = meta_with(Vec::new());
+	meta.info.name = String::from(name);
+	meta.info.hash = hash;
+	meta
+}
+
+/// A section of `kind` on `pages`, taken as it is.
+pub fn section(name: &str, pages: RangeInclusive<usize>, kind: PageKind) -> SectionMeta {
+	SectionMeta {
+		name: String::from(name),
+		pages,
+		range: None,
+		kind,
+		ops: Vec::new(),
+		columns: None,
+		emphasis: false,
+		sidebars: Sidebars::Include,
+		ocr: false,
+	}
+}
+
+/// A printing with `hash` on the same pages as the book.
+pub fn variant(name: &str, hash: u64) -> Variant {
+	Variant {
+		name: String::from(name),
+		hash,
+		fingerprint: None,
+		page_offset: 0,
+		pages: BTreeMap::new(),
+		page_labels: Vec::new(),
+	}
+}
+
+/// PDF pages from `start` on are printed from `first`, or unnumbered.
+pub fn label(start: usize, first: Option<usize>) -> PageLabel {
+	PageLabel { start, first }
+}
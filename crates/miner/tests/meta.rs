@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, fs::File, ops::RangeInclusive};
+
+use cofd_meta::{Op, PageKind, SectionMeta, SectionRange, Sidebars};
+use cofd_miner::{
+	backend::{PdfLine, PdfPage, PdfPages},
+	validate::{validate, validate_unique},
+	MetaRegistry,
+};
+
+mod common;
+use common::{meta, section, variant};
+
+#[test]
+fn registry_overrides() -> anyhow::Result<()> {
+	let dir = std::env::temp_dir().join(format!("cofd-miner-meta-{}", std::process::id()));
+	std::fs::create_dir_all(dir.join("fixes"))?;
+	let mut fixed = meta(1, "Fixed");
+	fixed.variants.push(variant("POD", 5));
+	for (file, meta) in [
+		("a.json", meta(1, "Shipped")),
+		("b.json", meta(2, "Other")),
+		("fixes/a.json", fixed),
+	] {
+		serde_json::to_writer(File::create(dir.join(file))?, &meta)?;
+	}
+	std::fs::write(dir.join("notes.txt"), "not a meta")?;
+	std::fs::write(dir.join("broken.json.bak"), "{")?;
+
+	let registry = MetaRegistry::empty()
+		.with(meta(1, "Added"))
+		.with_dir(&dir)
+		.and_then(|registry| registry.with_file(dir.join("fixes/a.json")));
+	std::fs::write(dir.join("broken.json"), "{")?;
+	let broken = MetaRegistry::empty().with_dir(&dir);
+	std::fs::remove_dir_all(&dir)?;
+
+	let registry = registry?;
+	assert_eq!(registry.len(), 2);
+	assert_eq!(
+		registry
+			.iter()
+			.map(|meta| &meta.info.name)
+			.collect::<Vec<_>>(),
+		["Other", "Fixed"]
+	);
+	assert_eq!(registry.get_by_hash(1).unwrap().info.name, "Fixed");
+	assert_eq!(registry.path(1), Some(dir.join("fixes/a.json").as_path()));
+	assert_eq!(registry.path(2), Some(dir.join("b.json").as_path()));
+	assert_eq!(registry.path(5), Some(dir.join("fixes/a.json").as_path()));
+	assert!(registry.get_by_hash(3).is_none());
+
+	assert!(broken.unwrap_err().to_string().contains("broken.json"));
+
+	// A meta whose other printing is a book already in the registry replaces it too.
+	let mut reprint = meta(4, "Reprint");
+	reprint.variants.push(variant("POD", 2));
+	let registry = registry.with(reprint);
+	assert_eq!(
+		registry
+			.iter()
+			.map(|meta| &meta.info.name)
+			.collect::<Vec<_>>(),
+		["Fixed", "Reprint"]
+	);
+	assert_eq!(registry.get_by_hash(2).unwrap().info.name, "Reprint");
+
+	Ok(())
+}
+
+/// A page with `lines`, where those starting with `|` are in a box.
+fn page(lines: &[&str]) -> PdfPage {
+	PdfPage {
+		lines: lines
+			.iter()
+			.map(|text| PdfLine {
+				boxed: text.starts_with('|'),
+				..PdfLine::from(text.trim_start_matches('|'))
+			})
+			.collect(),
+		..PdfPage::default()
+	}
+}
+
+/// A merits section on `pages`.
+fn merits(name: &str, pages: RangeInclusive<usize>) -> SectionMeta {
+	section(name, pages, PageKind::Merit(None))
+}
+
+#[test]
+fn validation() {
+	let pages: PdfPages = BTreeMap::from([
+		(0, page(&["Cover"])),
+		(1, page(&["Merits", "Giant (•••)"])),
+		(2, page(&["\tThe character is huge."])),
+	]);
+	let regex = |regex| Some(SectionRange::Regex(regex::Regex::new(regex).unwrap()));
+
+	let mut good = meta(1, "Good");
+	good.sections = vec![
+		SectionMeta {
+			range: regex("Giant"),
+			ops: vec![Op::Delete { range: 0..=3 }],
+			..merits("Merits", 1..=2)
+		},
+		SectionMeta {
+			range: Some(SectionRange::Range(0..1)),
+			..merits("Cover", 0..=1)
+		},
+	];
+	let report = validate(&good, Some(&pages));
+	assert!(report.is_empty(), "{:?}", report.problems);
+
+	let mut bad = meta(2, "Bad");
+	bad.sections = vec![
+		merits("Inverted", 2..=1),
+		merits("Past the end", 2..=3),
+		SectionMeta {
+			range: regex("Werewolf"),
+			ops: vec![Op::Delete { range: 5..=4 }],
+			..merits("Regex", 1..=2)
+		},
+		SectionMeta {
+			range: Some(SectionRange::Range(0..4)),
+			..merits("Lines", 1..=2)
+		},
+		SectionMeta {
+			ops: vec![
+				Op::RegexReplace {
+					regex: regex::Regex::new("Werewolf").unwrap(),
+					replace: String::new(),
+				},
+				Op::Insert {
+					pos: 100,
+					char: 'x',
+				},
+			],
+			..merits("Ops", 2..=2)
+		},
+	];
+	let report = validate(&bad, Some(&pages));
+	let sections: Vec<_> = report
+		.errors()
+		.map(|problem| problem.section.as_deref().unwrap())
+		.collect();
+	assert_eq!(
+		sections,
+		[
+			"Inverted",
+			"Past the end",
+			"Regex",
+			"Regex",
+			"Lines",
+			"Ops",
+			"Ops"
+		]
+	);
+	// The inverted op is reported once, and not applied.
+	assert_eq!(
+		report
+			.errors()
+			.filter(|problem| problem.reason.contains("5..=4"))
+			.count(),
+		1
+	);
+	// Past the end and Ops share page 2, and neither has a range.
+	assert_eq!(report.warnings().count(), 1);
+
+	let without_text = validate(&bad, None);
+	assert_eq!(without_text.errors().count(), 2);
+
+	let report = validate_unique([&good, &meta(1, "Copy")]);
+	assert_eq!(report.problems.len(), 1);
+	assert_eq!(report.problems[0].book, "Copy");
+
+	let mut reprint = meta(3, "Reprint");
+	reprint.variants.push(variant("POD", 3));
+	let report = validate_unique([&good, &reprint]);
+	assert_eq!(report.problems.len(), 1);
+	assert_eq!(
+		report.problems[0].reason,
+		"variant \"POD\" has the same hash 3 as the book"
+	);
+}
+
+#[test]
+fn validation_sidebars() {
+	let pages: PdfPages =
+		BTreeMap::from([(0, page(&["Merits", "|Playing It Big", "Giant (•••)"]))]);
+	let meta_with = |sidebars| {
+		let mut meta = meta(1, "Sidebars");
+		meta.sections = vec![SectionMeta {
+			sidebars,
+			ops: vec![Op::RegexReplace {
+				regex: regex::Regex::new("Playing It Big").unwrap(),
+				replace: String::new(),
+			}],
+			..merits("Merits", 0..=0)
+		}];
+		meta
+	};
+
+	assert!(validate(&meta_with(Sidebars::Include), Some(&pages)).is_empty());
+	// The op's text is in a sidebar, which the parse takes out before applying it.
+	let report = validate(&meta_with(Sidebars::Exclude), Some(&pages));
+	assert_eq!(report.errors().count(), 1);
+}
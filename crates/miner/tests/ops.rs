@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use cofd_meta::{Op, PageKind, SectionMeta};
+use cofd_miner::{
+	backend::{PdfLine, PdfPage},
+	error::CofDMinerError,
+	process_section,
+	source::Section,
+};
+use regex::Regex;
+
+mod common;
+use common::section;
+
+fn run(text: &str, ops: Vec<Op>) -> anyhow::Result<String> {
+	run_section(text, ops).map(|section| section.extract)
+}
+
+fn run_section(text: &str, ops: Vec<Op>) -> anyhow::Result<Section> {
+	let pages = BTreeMap::from([(
+		0,
+		PdfPage {
+			lines: text.split('\n').map(PdfLine::from).collect(),
+			..PdfPage::default()
+		},
+	)]);
+
+	process_section(
+		&pages,
+		&SectionMeta {
+			ops,
+			..section("Test", 0..=0, PageKind::default())
+		},
+		false,
+	)
+}
+
+#[test]
+fn ops_apply_in_order() -> anyhow::Result<()> {
+	let extract = run(
+		"Hello world",
+		vec![
+			Op::Delete { range: 0..=5 },
+			Op::Insert { pos: 5, char: '!' },
+			Op::Replace {
+				range: 0..=0,
+				replace: String::from("W"),
+			},
+			Op::RegexReplace {
+				regex: Regex::new("!$")?,
+				replace: String::from("?"),
+			},
+		],
+	)?;
+
+	assert_eq!(extract, "World?");
+	Ok(())
+}
+
+#[test]
+fn move_op() -> anyhow::Result<()> {
+	assert_eq!(
+		run(
+			"abcdef",
+			vec![Op::Move {
+				range: 0..=1,
+				pos: 6
+			}]
+		)?,
+		"cdefab"
+	);
+	assert_eq!(
+		run(
+			"abcdef",
+			vec![Op::Move {
+				range: 4..=5,
+				pos: 0
+			}]
+		)?,
+		"efabcd"
+	);
+	Ok(())
+}
+
+#[test]
+fn offsets_follow_ops() -> anyhow::Result<()> {
+	let section = run_section(
+		"abc def ghi",
+		vec![
+			Op::Delete { range: 0..=3 },
+			Op::RegexReplace {
+				regex: Regex::new("e")?,
+				replace: String::from("EEE"),
+			},
+			Op::Move {
+				range: 0..=5,
+				pos: 9,
+			},
+		],
+	)?;
+	assert_eq!(section.extract, "ghidEEEf ");
+
+	let original: Vec<usize> = (0..=section.extract.len())
+		.map(|pos| section.to_original(pos))
+		.collect();
+	assert_eq!(original, [8, 9, 10, 4, 5, 5, 5, 6, 7, 11]);
+
+	// The same text twice is told apart by where it is in `extract`.
+	let section = run_section("x\nRepeat\ny\nRepeat", vec![Op::Delete { range: 0..=1 }])?;
+	let second = section.extract.rfind("Repeat").unwrap();
+	assert_eq!(section.find_original(&section.extract[second..]), Some(11));
+
+	// A saved section keeps its offsets, and one without them isn't read as all zeroes.
+	let mut json = serde_json::to_value(&section)?;
+	assert_eq!(
+		serde_json::from_value::<Section>(json.clone())?.offsets,
+		section.offsets
+	);
+	json.as_object_mut().unwrap().remove("offsets");
+	assert!(serde_json::from_value::<Section>(json).is_err());
+
+	Ok(())
+}
+
+#[test]
+fn out_of_range_op() {
+	let err = run(
+		"abc",
+		vec![Op::Delete { range: 0..=0 }, Op::Delete { range: 1..=5 }],
+	)
+	.unwrap_err();
+
+	match err.downcast_ref::<CofDMinerError>() {
+		Some(CofDMinerError::InvalidOp { section, index, .. }) => {
+			assert_eq!(section, "Test");
+			assert_eq!(*index, 1);
+		}
+		_ => panic!("unexpected error: {err}"),
+	}
+}
+
+#[test]
+fn vtr_rating_fixup() -> anyhow::Result<()> {
+	let meta: cofd_meta::SourceMeta =
+		serde_json::from_str(include_str!("../../../meta/Vampire the Requiem 2e.json"))?;
+	let ops = meta.sections[0].ops.clone();
+
+	// Only the broken header is touched, not other lines ending in dots.
+	let extract = run(
+		"Swarm Form ••\nEffect: Your character breaks into a swarm.\nDisciplines ••",
+		ops,
+	)?;
+	assert_eq!(
+		extract,
+		"Swarm Form (••)\nEffect: Your character breaks into a swarm.\nDisciplines ••"
+	);
+	Ok(())
+}
@@ -0,0 +1,536 @@
+use std::collections::BTreeMap;
+
+use cofd_meta::{PageKind, TableKind};
+use cofd_miner::{
+	backend::{PdfLine, PdfPage, Rect, Table},
+	parse::{ParsedBook, ParserRegistry, PdfExtract, SectionParser, Severity},
+	process_section,
+	schema::{ArmorRating, DisciplineRating},
+	source::Section,
+};
+use cofd_schema::{
+	book::{Book, BookInfo},
+	item::gift::GiftKind,
+	template::werewolf::Auspice,
+};
+
+mod common;
+use common::section;
+
+fn extract(kind: PageKind, text: &str) -> anyhow::Result<PdfExtract> {
+	extract_pages(kind, &[text])
+}
+
+/// A section over one page for each of `texts`, numbered from 1.
+fn extract_pages(kind: PageKind, texts: &[&str]) -> anyhow::Result<PdfExtract> {
+	let pages: BTreeMap<usize, PdfPage> = texts
+		.iter()
+		.enumerate()
+		.map(|(i, text)| {
+			(
+				i,
+				PdfPage {
+					lines: text.split('\n').map(PdfLine::from).collect(),
+					..PdfPage::default()
+				},
+			)
+		})
+		.collect();
+	let mut section = process_section(&pages, &section("Test", 0..=texts.len() - 1, kind), false)?;
+	section.printed_pages = (0..texts.len()).map(|i| (i, i + 1)).collect();
+
+	Ok(PdfExtract {
+		info: BookInfo::default(),
+		sections: vec![section],
+		hyphenation: Vec::new(),
+	})
+}
+
+fn parse(kind: PageKind, text: &str) -> anyhow::Result<Book> {
+	Ok(parse_full(kind, text)?.book)
+}
+
+fn parse_full(kind: PageKind, text: &str) -> anyhow::Result<ParsedBook> {
+	let parsed = extract(kind, text)?.parse();
+	assert!(
+		!parsed.report.has_errors(),
+		"{:?}",
+		parsed.report.diagnostics
+	);
+
+	Ok(parsed)
+}
+
+#[test]
+fn mage_spells() -> anyhow::Result<()> {
+	let book = parse(
+		PageKind::MageSpell,
+		"Death
+Corpse Mask (Death ••)
+Practice: Weaving
+Primary Factor: Duration
+Suggested Rote Skills: Crafts, Medicine, Subterfuge
+	The mage can change the appearance of a corpse.
++1 Reach: The mage may also alter the corpse's scent.
++2 Reach: The change is permanent.
+Ghostly Grip (Death •)
+Practice: Knowing
+Primary Factor: Potency
+Withstand: Resolve
+Suggested Rote Skills: Occult, Investigation
+	The mage reaches out to a ghost.",
+	)?;
+
+	let spells = &book.mage_spells;
+	assert_eq!(spells.len(), 2);
+
+	assert_eq!(spells[0].name, "Corpse Mask");
+	assert_eq!(spells[0].inner.level, 2);
+	assert_eq!(spells[0].inner.suggested_rote_skills.len(), 3);
+	assert_eq!(spells[0].inner.reach.len(), 2);
+
+	assert_eq!(spells[1].name, "Ghostly Grip");
+	assert_eq!(spells[1].inner.level, 1);
+	assert_eq!(spells[1].inner.withstand.as_deref(), Some("Resolve"));
+	assert!(spells[1].inner.reach.is_empty());
+
+	Ok(())
+}
+
+#[test]
+fn moon_gifts() -> anyhow::Result<()> {
+	let book = parse(
+		PageKind::Gift(GiftKind::Moon),
+		"Full Moon's Gift
+\tThe Rahu are warriors.
+\t• Killer Instinct
+\tThe Rahu knows where to strike.
+Cost: None
+Action: Reflexive
+\t•• Warrior's Hide
+\tThe Rahu shrugs off blows.
+Gibbous Moon's Gift
+\t• Infectious Hunt
+\tThe Cahalith sings of the hunt.",
+	)?;
+
+	let gifts = &book.moon_gifts;
+	assert_eq!(gifts.len(), 2);
+
+	assert_eq!(gifts[0].facets.len(), 2);
+	assert_eq!(gifts[0].facets[0].name, "Killer Instinct");
+	assert_eq!(gifts[0].facets[0].inner.inner.level, 1);
+	assert_eq!(gifts[0].facets[0].inner.inner.auspice, Auspice::Rahu);
+	assert!(gifts[0].facets[0].inner.action.is_some());
+	assert_eq!(gifts[0].facets[1].inner.inner.level, 2);
+
+	assert_eq!(gifts[1].facets.len(), 1);
+	assert_eq!(gifts[1].facets[0].inner.inner.auspice, Auspice::Cahalith);
+
+	Ok(())
+}
+
+#[test]
+fn broken_merit_keeps_its_styles() -> anyhow::Result<()> {
+	let parsed = extract(
+		PageKind::Merit(None),
+		"Giant (•••)
+\tThe character is huge.
+Boxing (• to)
+\tThe character knows how to box.
+Boxing (•): Body Blow
+\tThe character hits hard.",
+	)?
+	.parse();
+
+	// Merits are read from the bottom up, so when Boxing's rating, which has no upper end,
+	// fails to parse, its style mustn't end up on the merit above it.
+	let giant = parsed
+		.book
+		.merits
+		.iter()
+		.find(|merit| merit.name == "Giant")
+		.unwrap();
+	assert!(giant.inner.children.is_empty());
+
+	Ok(())
+}
+
+#[test]
+fn gift_references() -> anyhow::Result<()> {
+	let parsed = extract_pages(
+		PageKind::Gift(GiftKind::Shadow),
+		&[
+			"Gift of Fire\n\tIts first facet, Call Flame (Glory), is the easiest.",
+			"Call Flame (Glory)\n\tThe werewolf sets things alight.",
+			"Fire Walk\n\t(Purity)\n\tThe werewolf walks through flame.",
+		],
+	)?
+	.parse();
+
+	// The name on the first page isn't the header, so it mustn't give the reference.
+	let [gift] = parsed.book.gifts.as_slice() else {
+		panic!("{:?}", parsed.book.gifts);
+	};
+	let facet = |name: &str| gift.facets.iter().find(|facet| facet.name == name).unwrap();
+	assert_eq!(facet("Call Flame").reference.1, 2);
+	// A renown on the line after the name is still part of the header.
+	assert_eq!(facet("Fire Walk").reference.1, 3);
+
+	Ok(())
+}
+
+struct LineParser;
+impl SectionParser for LineParser {
+	fn handles(&self, kind: &PageKind) -> bool {
+		matches!(kind, PageKind::Custom(kind) if kind == "Line")
+	}
+
+	fn parse(&self, section: &Section, out: &mut ParsedBook) {
+		for line in section.extract.lines() {
+			out.push_custom("Line", &line).unwrap();
+		}
+	}
+}
+
+#[test]
+fn custom_parser() -> anyhow::Result<()> {
+	let kind = PageKind::Custom(String::from("Line"));
+
+	let parsed = extract(kind.clone(), "a\nb")?.parse();
+	assert!(parsed.custom.is_empty());
+	assert_eq!(parsed.report.warnings().count(), 1);
+
+	let registry = ParserRegistry::default().with(LineParser);
+	let parsed = extract(kind, "a\nb")?.parse_with(&registry);
+	assert_eq!(parsed.custom::<String>("Line")?, ["a", "b"]);
+	assert!(parsed.report.is_empty());
+
+	Ok(())
+}
+
+#[test]
+fn parse_report() -> anyhow::Result<()> {
+	let parsed = extract(
+		PageKind::MageSpell,
+		"Corpse Mask (Death ••)
+Practice: Dancing
+Primary Factor: Duration
+	The mage can change the appearance of a corpse.",
+	)?
+	.parse();
+	assert!(parsed.book.mage_spells.is_empty());
+	assert!(parsed.report.has_errors());
+
+	let [error] = parsed.report.diagnostics.as_slice() else {
+		panic!("{:?}", parsed.report.diagnostics);
+	};
+	assert_eq!(error.severity, Severity::Error);
+	assert_eq!(error.section, "Test");
+	assert_eq!(error.page, Some(0));
+	assert_eq!(error.snippet, "Corpse Mask (Death ••)");
+	assert_eq!(error.reason, "Unknown practice \"Dancing\"");
+	assert_eq!(parsed.report.section("Test").count(), 1);
+	assert_eq!(parsed.report.section("Other").count(), 0);
+
+	// A diagnostic about the whole section isn't pinned to a page.
+	let parsed = extract(PageKind::Table(TableKind::Weapon), "a\nb")?.parse();
+	let [warning] = parsed.report.diagnostics.as_slice() else {
+		panic!("{:?}", parsed.report.diagnostics);
+	};
+	assert_eq!(warning.severity, Severity::Warning);
+	assert_eq!(warning.page, None);
+	assert!(!parsed.report.has_errors());
+	assert_eq!(
+		warning.to_string(),
+		"warning: Test: No tables found in section"
+	);
+
+	Ok(())
+}
+
+#[test]
+fn disciplines() -> anyhow::Result<()> {
+	let parsed = parse_full(
+		PageKind::Discipline(String::from("Animalism")),
+		"Animalism
+\t• Feral Whispers
+\tThe vampire can speak with animals.
+Cost: None
+Dice Pool: Manipulation + Animal Ken + Animalism
+Action: Instant
+Duration: One scene
+\t•• Raise the Familiar
+\tThe vampire calls a dead animal back.
+Cost: 1 Vitae
+Action: Instant",
+	)?;
+
+	let powers = &parsed.disciplines;
+	assert_eq!(powers.len(), 2);
+	assert_eq!(powers[0].name, "Feral Whispers");
+	assert_eq!(powers[0].inner.discipline, "Animalism");
+	assert_eq!(powers[0].inner.level, 1);
+	assert!(powers[0].inner.action.is_some());
+	assert_eq!(powers[1].inner.level, 2);
+
+	Ok(())
+}
+
+#[test]
+fn devotions() -> anyhow::Result<()> {
+	let parsed = parse_full(
+		PageKind::Devotion,
+		"Body of Will
+Prerequisites: Resilience ••, Vigor ••
+\tThe vampire pushes on.
+Cost: 1 Vitae
+Action: Reflexive
+Experience Cost: 2
+Bloodhound
+Prerequisites: Auspex •, Vigor •
+\tThe vampire tracks by scent.
+Cost: None
+Experience Cost: 1",
+	)?;
+
+	let devotions = &parsed.devotions;
+	assert_eq!(devotions.len(), 2);
+	assert_eq!(devotions[0].name, "Bloodhound");
+	assert_eq!(devotions[0].inner.experience_cost, Some(1));
+	assert_eq!(
+		devotions[1].inner.prerequisites,
+		[
+			DisciplineRating {
+				discipline: String::from("Resilience"),
+				level: 2
+			},
+			DisciplineRating {
+				discipline: String::from("Vigor"),
+				level: 2
+			},
+		]
+	);
+	assert!(devotions[1].inner.action.is_some());
+
+	Ok(())
+}
+
+#[test]
+fn embeds_and_exploits() -> anyhow::Result<()> {
+	let parsed = parse_full(
+		PageKind::Embed(Some(String::from("Messenger"))),
+		"Messenger Embeds
+Across a Crowded Room
+Dice Pool: Manipulation + Expression
+Action: Instant
+\tThe demon sends a message to one person.
+Exceptional Success: The message is also understood.
+Living Recorder
+Action: Reflexive
+\tThe demon remembers every word.",
+	)?;
+
+	let embeds = &parsed.embeds;
+	assert_eq!(embeds.len(), 2);
+	assert_eq!(embeds[0].name, "Across a Crowded Room");
+	assert_eq!(embeds[0].inner.incarnation.as_deref(), Some("Messenger"));
+	assert!(embeds[0].inner.action.is_some());
+	assert_eq!(embeds[1].name, "Living Recorder");
+
+	let parsed = parse_full(
+		PageKind::Exploit,
+		"Hellfire
+Cost: 1 Aether
+Dice Pool: Wits + Primum
+Action: Instant
+Duration: One turn
+\tThe demon calls down fire.",
+	)?;
+
+	assert_eq!(parsed.exploits.len(), 1);
+	assert_eq!(parsed.exploits[0].name, "Hellfire");
+	assert!(parsed.exploits[0].inner.action.is_some());
+
+	Ok(())
+}
+
+#[test]
+fn powers_described_first() -> anyhow::Result<()> {
+	let parsed = parse_full(
+		PageKind::Exploit,
+		"Exploits
+Hellfire
+\tThe demon calls down fire 
+From Above Strikes the Hand 
+of the Infrastructure.
+Cost: 1 Aether
+Dice Pool: Wits + Primum
+Action: Instant
+Merciful Blade
+\tThe demon strikes to spare.
+Sooner or Later
+Nothing Is Forgotten
+Action: Reflexive",
+	)?;
+
+	let names: Vec<_> = parsed.exploits.iter().map(|e| e.name.as_str()).collect();
+	assert_eq!(names, ["Hellfire", "Nothing Is Forgotten"]);
+	assert_eq!(
+		parsed.exploits[0].description,
+		["The demon calls down fire From Above Strikes the Hand of the Infrastructure."]
+	);
+	assert!(parsed.exploits[0].inner.action.is_some());
+
+	Ok(())
+}
+
+#[test]
+fn ciphers() -> anyhow::Result<()> {
+	let parsed = parse_full(
+		PageKind::Cipher,
+		"The Drowned Choir
+\tA Cipher for a demon who hides among singers.
+Interlock: Voice of the Angel
+Interlock: Across a Crowded Room
+Final Truth: Every song is a lock.",
+	)?;
+
+	let [cipher] = parsed.ciphers.as_slice() else {
+		panic!("{:?}", parsed.ciphers);
+	};
+	assert_eq!(cipher.name, "The Drowned Choir");
+	assert_eq!(
+		cipher.inner.interlocks,
+		["Voice of the Angel", "Across a Crowded Room"]
+	);
+	assert_eq!(cipher.inner.final_truth, ["Every song is a lock."]);
+
+	Ok(())
+}
+
+#[test]
+fn hyphenation() -> anyhow::Result<()> {
+	let text = "Living Recorder
+Action: Reflexive
+\tA well-known sleep-
+walker, the God-
+Machine's agent is well-
+known. No sleepwalker sees a half-
+elf.";
+	let paragraphs = |parsed: &ParsedBook| {
+		let embed = &parsed.embeds[0];
+		embed
+			.description
+			.iter()
+			.chain(&embed.effects)
+			.cloned()
+			.collect::<Vec<_>>()
+	};
+
+	let parsed = extract(PageKind::Embed(None), text)?.parse();
+	assert_eq!(
+		paragraphs(&parsed),
+		["A well-known sleepwalker, the God-Machine's agent is well-known. No sleepwalker sees a halfelf."]
+	);
+
+	let mut extract = extract(PageKind::Embed(None), text)?;
+	extract.hyphenation = vec![
+		String::from("Sleep-walker"),
+		String::from("wellknown"),
+		String::from("half-elf"),
+	];
+	assert_eq!(
+		paragraphs(&extract.parse()),
+		["A well-known sleep-walker, the God-Machine's agent is wellknown. No sleepwalker sees a half-elf."]
+	);
+
+	Ok(())
+}
+
+#[test]
+fn armor_table() -> anyhow::Result<()> {
+	let bbox = Rect {
+		x0: 72.0,
+		y0: 100.0,
+		x1: 500.0,
+		y1: 160.0,
+	};
+	let row = |cells: [&str; 7]| cells.map(String::from).to_vec();
+	let pages = BTreeMap::from([(
+		3,
+		PdfPage {
+			lines: vec![PdfLine {
+				bbox,
+				..PdfLine::from("Type Rating Strength Defense Speed Availability Coverage")
+			}],
+			tables: vec![Table {
+				bbox,
+				header: row([
+					"Type",
+					"Rating",
+					"Strength",
+					"Defense",
+					"Speed",
+					"Availability",
+					"Coverage",
+				]),
+				rows: vec![
+					row([
+						"Reinforced clothing",
+						"1/0",
+						"1",
+						"0",
+						"0",
+						"•",
+						"Torso, arms, legs",
+					]),
+					row(["Kevlar vest", "1/3", "1", "0", "0", "•", "Torso"]),
+					row([
+						"Riot gear",
+						"3/5",
+						"3",
+						"−2",
+						"−1",
+						"•••",
+						"Torso, arms, legs",
+					]),
+				],
+				..Table::default()
+			}],
+			..PdfPage::default()
+		},
+	)]);
+	let mut section = process_section(
+		&pages,
+		&section("Armor", 3..=3, PageKind::Table(TableKind::Armor)),
+		false,
+	)?;
+	section.printed_pages = BTreeMap::from([(3, 57)]);
+	let parsed = PdfExtract {
+		info: BookInfo::default(),
+		sections: vec![section],
+		hyphenation: Vec::new(),
+	}
+	.parse();
+	assert!(parsed.report.is_empty(), "{:?}", parsed.report.diagnostics);
+
+	let armor = &parsed.armor;
+	assert_eq!(armor.len(), 3);
+	assert_eq!(armor[0].name, "Kevlar vest");
+	assert_eq!(
+		armor[0].inner.rating,
+		ArmorRating {
+			general: 1,
+			ballistic: 3
+		}
+	);
+	assert_eq!(armor[0].reference.1, 57);
+	assert_eq!(armor[2].name, "Riot gear");
+	assert_eq!(armor[2].inner.defense, -2);
+	assert_eq!(armor[2].inner.speed, -1);
+	assert_eq!(armor[2].inner.availability, 3);
+	assert_eq!(armor[2].inner.coverage, ["Torso", "arms", "legs"]);
+
+	Ok(())
+}
@@ -103,9 +103,11 @@ fn pdf_extract() -> Result<()> {
 				.find(|b| b.info.hash.eq(&hash))
 				.map(|el| (entry, hash, el.clone()))
 		})
-		.flat_map(|(entry, hash, b)| cofd_miner::get_meta(hash).map(|meta| (entry, meta, b)))
+		.flat_map(|(entry, hash, b)| {
+			cofd_miner::get_meta_by_hash(hash).map(|meta| (entry, meta, b))
+		})
 		.flat_map(|(entry, meta, b)| {
-			cofd_miner::parse_book_with_meta(entry.path(), meta).map(|book| (book, b))
+			cofd_miner::parse_book_with_meta(entry.path(), &meta).map(|parsed| (parsed.book, b))
 		})
 		.collect();
 
@@ -3,7 +3,7 @@ use cofd_schema::book::Book;
 
 #[test]
 fn roundtrip() -> anyhow::Result<()> {
-	let book = parse_book("../pdf/Mage/Mage the Awakening 2e.pdf")?;
+	let book = parse_book("../pdf/Mage/Mage the Awakening 2e.pdf")?.book;
 
 	let _book: Book = serde_json::de::from_str(&serde_json::ser::to_string(&book)?)?;
 	println!("RON");